@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    hint::black_box,
+    io::SeekFrom,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use eyre::{bail, Result};
+use futures::{future::try_join_all, Stream, StreamExt};
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader},
+};
+use tokio_stream::wrappers::SplitStream;
+
+use crate::{
+    hash::{Digests, Hash, HashMode, MODES},
+    partition_wordlist,
+    status::{si, Progress},
+    worker::{self, visit_words},
+    Entry, Targets,
+};
+
+const BENCHMARK_TIME: Duration = Duration::from_secs(1);
+const WORDS: usize = 1 << 20;
+
+fn targets(mode: HashMode) -> Targets {
+    let params = mode.sample().expect("benchmarked modes have sample params");
+    let hash = vec![0; mode.digest_len()];
+    let entry = Entry {
+        user: None,
+        hash: hex::encode(&hash),
+    };
+    HashMap::from([((mode, params), Digests::from_iter([(hash, vec![entry])]))])
+}
+
+// Hashes in a tight loop on every core, without reading and against no
+// targets.
+fn raw_speed(mode: HashMode, words: &[Vec<u8>], n: usize) -> f64 {
+    let params = mode.sample().expect("benchmarked modes have sample params");
+    let digests = Digests::default();
+    let stop = AtomicBool::new(false);
+    let tested = AtomicU64::new(0);
+    let started = Instant::now();
+    std::thread::scope(|s| {
+        for i in 0..n {
+            let (params, digests, stop, tested) = (&params, &digests, &stop, &tested);
+            s.spawn(move || {
+                let mut count = 0;
+                let mut digest = Hash::new();
+                for word in words.iter().skip(i).step_by(n).cycle() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    black_box(mode.verify(word, params, digests, &mut digest));
+                    count += 1;
+                }
+                tested.fetch_add(count, Ordering::Relaxed);
+            });
+        }
+        std::thread::sleep(BENCHMARK_TIME);
+        stop.store(true, Ordering::Relaxed);
+    });
+    tested.into_inner() as f64 / started.elapsed().as_secs_f64()
+}
+
+// The per-line async streams dictionary attacks used before the batched
+// workers, kept as a baseline for them.
+async fn read_wordlist(
+    path: &str,
+    ranges: &[(u64, u64)],
+) -> Result<Vec<impl Stream<Item = (u64, Vec<u8>)>>> {
+    let mut streams = Vec::with_capacity(ranges.len());
+    for &(start, end) in ranges {
+        let mut f = File::open(path).await?;
+        f.seek(SeekFrom::Start(start)).await?;
+        let r = BufReader::new(f.take(end - start));
+        streams.push(SplitStream::new(r.split(b'\n')).scan(start, |offset, l| {
+            let mut l = l.unwrap();
+            let word_offset = *offset;
+            *offset += l.len() as u64 + 1;
+            if l.last() == Some(&b'\r') {
+                l.pop();
+            }
+            futures::future::ready(Some((word_offset, l)))
+        }));
+    }
+    Ok(streams)
+}
+
+fn find_hits(targets: &Targets, password: &[u8], digest: &mut Hash) -> Vec<(HashMode, Entry)> {
+    let mut hits = Vec::new();
+    for ((mode, params), hashes) in targets {
+        if let Some(entries) = mode.verify(password, params, hashes, digest) {
+            hits.extend(entries.iter().map(|entry| (*mode, entry.clone())));
+        }
+    }
+    hits
+}
+
+// Rereads the wordlist through the baseline streams until time is up.
+async fn stream_speed(mode: HashMode, path: &str, ranges: &[(u64, u64)]) -> Result<f64> {
+    let targets = Arc::new(targets(mode));
+    let stop = Arc::new(AtomicBool::new(false));
+    let tested = Arc::new(AtomicU64::new(0));
+    let mut tasks = Vec::new();
+    let started = Instant::now();
+    for &range in ranges {
+        let path = path.to_string();
+        let targets = targets.clone();
+        let stop = stop.clone();
+        let tested = tested.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut count = 0;
+            let mut digest = Hash::new();
+            'reread: loop {
+                let mut chunk = read_wordlist(&path, &[range]).await?.remove(0);
+                while let Some((_, password)) = chunk.next().await {
+                    if stop.load(Ordering::Relaxed) {
+                        break 'reread;
+                    }
+                    if mode.slow() {
+                        let targets = targets.clone();
+                        tokio::task::spawn_blocking(move || {
+                            find_hits(&targets, &password, &mut Hash::new())
+                        })
+                        .await?;
+                    } else {
+                        black_box(find_hits(&targets, &password, &mut digest));
+                    }
+                    count += 1;
+                }
+            }
+            tested.fetch_add(count, Ordering::Relaxed);
+            Ok::<_, eyre::Report>(())
+        }));
+    }
+    // Busy workers can starve the runtime's timers.
+    std::thread::spawn(move || {
+        std::thread::sleep(BENCHMARK_TIME);
+        stop.store(true, Ordering::Relaxed);
+    });
+    try_join_all(tasks)
+        .await?
+        .into_iter()
+        .collect::<Result<()>>()?;
+    Ok(tested.load(Ordering::Relaxed) as f64 / started.elapsed().as_secs_f64())
+}
+
+// Goes through the same workers and target lookups as a dictionary attack,
+// rereading the wordlist until time is up.
+fn batched_speed(mode: HashMode, path: &str, ranges: &[(u64, u64)]) -> Result<f64> {
+    let targets = targets(mode);
+    let stop = Arc::new(AtomicBool::new(false));
+    let progress = Progress::new(ranges.to_vec(), 1);
+    let sources = ranges
+        .iter()
+        .map(|&range| {
+            let path = path.to_string();
+            let stop = stop.clone();
+            worker::source(move |visit| {
+                while !stop.load(Ordering::Relaxed) {
+                    visit_words(&path, range, |position, word| visit.push(position, word))?;
+                }
+                Ok(())
+            })
+        })
+        .collect();
+    let started = Instant::now();
+    {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(BENCHMARK_TIME);
+            stop.store(true, Ordering::Relaxed);
+        });
+    }
+    worker::run(&targets, sources, &progress, &stop, &|_, _, _| Ok(()))?;
+    Ok(progress.tested() as f64 / started.elapsed().as_secs_f64())
+}
+
+pub async fn benchmark(mode: Option<HashMode>) -> Result<()> {
+    let modes = match mode {
+        Some(mode) if mode.sample().is_none() => bail!("{mode} cannot be benchmarked on its own"),
+        Some(mode) => vec![mode],
+        None => MODES
+            .iter()
+            .copied()
+            .filter(|mode| mode.sample().is_some())
+            .collect(),
+    };
+    let words = (0..WORDS)
+        .map(|i| format!("{i:08}").into_bytes())
+        .collect::<Vec<_>>();
+    let path = std::env::temp_dir().join(format!("scream-benchmark-{}", std::process::id()));
+    let path = path.to_string_lossy().into_owned();
+    tokio::fs::write(&path, [words.join(&b'\n'), b"\n".to_vec()].concat()).await?;
+    let result = async {
+        let ranges = partition_wordlist(&path, num_cpus::get()).await?;
+        println!("{} CPUs, {BENCHMARK_TIME:?} per mode", ranges.len());
+        println!(
+            "{:<16} {:>12} {:>12} {:>12} {:>8}",
+            "mode", "raw H/s", "stream H/s", "batched H/s", "speedup"
+        );
+        for mode in modes {
+            let raw = raw_speed(mode, &words, ranges.len());
+            let stream = stream_speed(mode, &path, &ranges).await?;
+            let batched = batched_speed(mode, &path, &ranges)?;
+            println!(
+                "{:<16} {:>12} {:>12} {:>12} {:>7.2}x",
+                mode.name(),
+                si(raw),
+                si(stream),
+                si(batched),
+                batched / stream
+            );
+        }
+        Ok(())
+    }
+    .await;
+    let _ = tokio::fs::remove_file(&path).await;
+    result
+}
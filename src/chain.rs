@@ -0,0 +1,201 @@
+use eyre::{bail, eyre, Result};
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Func {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        let func = match name {
+            "md5" => Func::Md5,
+            "sha1" => Func::Sha1,
+            "sha224" => Func::Sha224,
+            "sha256" => Func::Sha256,
+            "sha384" => Func::Sha384,
+            "sha512" => Func::Sha512,
+            _ => return None,
+        };
+        Some(func)
+    }
+
+    fn digest(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Func::Md5 => md5::compute(data).to_vec(),
+            Func::Sha1 => Sha1::digest(data).to_vec(),
+            Func::Sha224 => Sha224::digest(data).to_vec(),
+            Func::Sha256 => Sha256::digest(data).to_vec(),
+            Func::Sha384 => Sha384::digest(data).to_vec(),
+            Func::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Expr {
+    Pass,
+    Salt,
+    Literal(Vec<u8>),
+    Concat(Vec<Expr>),
+    Apply {
+        func: Func,
+        times: u32,
+        arg: Box<Expr>,
+    },
+}
+
+impl Expr {
+    fn salted(&self) -> bool {
+        match self {
+            Expr::Salt => true,
+            Expr::Pass | Expr::Literal(_) => false,
+            Expr::Concat(parts) => parts.iter().any(Expr::salted),
+            Expr::Apply { arg, .. } => arg.salted(),
+        }
+    }
+
+    // Like PHP's md5()/sha1(), inner digests are fed onwards as lowercase hex,
+    // only the outermost one is compared raw.
+    fn eval(&self, pass: &[u8], salt: &[u8], raw: bool) -> Vec<u8> {
+        match self {
+            Expr::Pass => pass.to_vec(),
+            Expr::Salt => salt.to_vec(),
+            Expr::Literal(literal) => literal.clone(),
+            Expr::Concat(parts) => parts
+                .iter()
+                .flat_map(|part| part.eval(pass, salt, false))
+                .collect(),
+            Expr::Apply { func, times, arg } => {
+                let mut data = arg.eval(pass, salt, false);
+                for i in 0..*times {
+                    data = func.digest(&data);
+                    if !raw || i + 1 < *times {
+                        data = hex::encode(data).into_bytes();
+                    }
+                }
+                data
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if !self.eat(token) {
+            bail!("expected {token:?} at offset {}", self.pos);
+        }
+        Ok(())
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &str {
+        let start = self.pos;
+        let len = self.rest().find(|c| !f(c)).unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.src[start..self.pos]
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut parts = vec![self.term()?];
+        while self.eat(".") {
+            parts.push(self.term()?);
+        }
+        Ok(match parts.len() {
+            1 => parts.pop().unwrap(),
+            _ => Expr::Concat(parts),
+        })
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        if self.eat("$pass") || self.eat("$p") {
+            return Ok(Expr::Pass);
+        }
+        if self.eat("$salt") || self.eat("$s") {
+            return Ok(Expr::Salt);
+        }
+        if self.eat("'") {
+            let literal = self.take_while(|c| c != '\'').as_bytes().to_vec();
+            self.expect("'")?;
+            return Ok(Expr::Literal(literal));
+        }
+        let start = self.pos;
+        let name = self.take_while(|c| c.is_ascii_alphanumeric());
+        let func = Func::from_name(name)
+            .ok_or_else(|| eyre!("unknown function {name:?} at offset {start}"))?;
+        let times = if self.eat("^") {
+            let start = self.pos;
+            let times = self.take_while(|c| c.is_ascii_digit());
+            match times.parse() {
+                Ok(times) if times > 0 => times,
+                _ => bail!("invalid iteration count at offset {start}"),
+            }
+        } else {
+            1
+        };
+        self.expect("(")?;
+        let arg = Box::new(self.expr()?);
+        self.expect(")")?;
+        Ok(Expr::Apply { func, times, arg })
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Chain(Expr);
+
+impl Chain {
+    pub fn parse(src: &str) -> Result<Self> {
+        let mut parser = Parser { src, pos: 0 };
+        let expr = parser.expr()?;
+        parser.skip_whitespace();
+        if !parser.rest().is_empty() {
+            bail!("unexpected {:?} at offset {}", parser.rest(), parser.pos);
+        }
+        if !matches!(expr, Expr::Apply { .. }) {
+            bail!("a hash chain must end in a hash function");
+        }
+        Ok(Self(expr))
+    }
+
+    pub fn salted(&self) -> bool {
+        self.0.salted()
+    }
+
+    pub fn eval(&self, pass: &[u8], salt: &[u8]) -> Vec<u8> {
+        self.0.eval(pass, salt, true)
+    }
+
+    // The outermost function fixes the length whatever the input.
+    pub fn digest_len(&self) -> usize {
+        self.eval(b"", b"").len()
+    }
+}
@@ -0,0 +1,256 @@
+use sha2::{Digest, Sha256, Sha512};
+
+const ITOA64: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+const MD5_ORDER: &[(usize, usize, usize)] =
+    &[(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)];
+
+const SHA256_ORDER: &[(usize, usize, usize)] = &[
+    (0, 10, 20),
+    (21, 1, 11),
+    (12, 22, 2),
+    (3, 13, 23),
+    (24, 4, 14),
+    (15, 25, 5),
+    (6, 16, 26),
+    (27, 7, 17),
+    (18, 28, 8),
+    (9, 19, 29),
+];
+
+const SHA512_ORDER: &[(usize, usize, usize)] = &[
+    (0, 21, 42),
+    (22, 43, 1),
+    (44, 2, 23),
+    (3, 24, 45),
+    (25, 46, 4),
+    (47, 5, 26),
+    (6, 27, 48),
+    (28, 49, 7),
+    (50, 8, 29),
+    (9, 30, 51),
+    (31, 52, 10),
+    (53, 11, 32),
+    (12, 33, 54),
+    (34, 55, 13),
+    (56, 14, 35),
+    (15, 36, 57),
+    (37, 58, 16),
+    (59, 17, 38),
+    (18, 39, 60),
+    (40, 61, 19),
+    (62, 20, 41),
+];
+
+pub const SHA_ROUNDS_DEFAULT: u32 = 5000;
+
+fn push_b64(out: &mut Vec<u8>, mut w: u32, n: usize) {
+    for _ in 0..n {
+        out.push(ITOA64[(w & 0x3f) as usize]);
+        w >>= 6;
+    }
+}
+
+// The crypt(3) family encodes the final digest in a scrambled byte order;
+// `tail` holds the bytes left over after the full 3-byte groups.
+fn encode(digest: &[u8], order: &[(usize, usize, usize)], tail: &[usize]) -> Vec<u8> {
+    let mut out = Vec::with_capacity((digest.len() * 4).div_ceil(3));
+    for &(a, b, c) in order {
+        let w = (digest[a] as u32) << 16 | (digest[b] as u32) << 8 | digest[c] as u32;
+        push_b64(&mut out, w, 4);
+    }
+    let w = tail.iter().fold(0u32, |w, &i| w << 8 | digest[i] as u32);
+    push_b64(&mut out, w, tail.len() + 1);
+    out
+}
+
+pub fn md5_crypt(password: &[u8], salt: &[u8]) -> Vec<u8> {
+    let salt = &salt[..salt.len().min(8)];
+    let alt = md5::compute([password, salt, password].concat());
+    let mut ctx = md5::Context::new();
+    ctx.consume(password);
+    ctx.consume(b"$1$");
+    ctx.consume(salt);
+    for chunk in password.chunks(16) {
+        ctx.consume(&alt[..chunk.len()]);
+    }
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 == 1 {
+            ctx.consume([0]);
+        } else {
+            ctx.consume(&password[..1]);
+        }
+        i >>= 1;
+    }
+    let mut digest = ctx.compute();
+    for i in 0..1000 {
+        let mut ctx = md5::Context::new();
+        if i & 1 == 1 {
+            ctx.consume(password);
+        } else {
+            ctx.consume(*digest);
+        }
+        if i % 3 != 0 {
+            ctx.consume(salt);
+        }
+        if i % 7 != 0 {
+            ctx.consume(password);
+        }
+        if i & 1 == 1 {
+            ctx.consume(*digest);
+        } else {
+            ctx.consume(password);
+        }
+        digest = ctx.compute();
+    }
+    encode(&*digest, MD5_ORDER, &[11])
+}
+
+fn repeat_to(block: &[u8], len: usize) -> Vec<u8> {
+    block.iter().copied().cycle().take(len).collect()
+}
+
+fn sha_crypt<D: Digest>(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let salt = &salt[..salt.len().min(16)];
+    let alt = D::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+    let mut ctx = D::new().chain_update(password).chain_update(salt);
+    ctx.update(repeat_to(&alt, password.len()));
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 == 1 {
+            ctx.update(&alt);
+        } else {
+            ctx.update(password);
+        }
+        i >>= 1;
+    }
+    let mut digest = ctx.finalize();
+
+    let mut dp = D::new();
+    for _ in 0..password.len() {
+        dp.update(password);
+    }
+    let p = repeat_to(&dp.finalize(), password.len());
+    let mut ds = D::new();
+    for _ in 0..16 + digest[0] as usize {
+        ds.update(salt);
+    }
+    let s = repeat_to(&ds.finalize(), salt.len());
+
+    for i in 0..rounds {
+        let mut ctx = D::new();
+        if i & 1 == 1 {
+            ctx.update(&p);
+        } else {
+            ctx.update(&digest);
+        }
+        if i % 3 != 0 {
+            ctx.update(&s);
+        }
+        if i % 7 != 0 {
+            ctx.update(&p);
+        }
+        if i & 1 == 1 {
+            ctx.update(&digest);
+        } else {
+            ctx.update(&p);
+        }
+        digest = ctx.finalize();
+    }
+    digest.to_vec()
+}
+
+pub fn sha256_crypt(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let digest = sha_crypt::<Sha256>(password, salt, rounds);
+    encode(&digest, SHA256_ORDER, &[31, 30])
+}
+
+pub fn sha512_crypt(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let digest = sha_crypt::<Sha512>(password, salt, rounds);
+    encode(&digest, SHA512_ORDER, &[63])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference hashes from glibc crypt(3).
+    #[test]
+    fn md5_crypt_known_answers() {
+        for (password, salt, hash) in [
+            ("Hello world!", "saltstring", "YMyguxXMBpd2TEZ.vS/3q1"),
+            ("password", "abc", "BXBqpb9BZcZhXLgbee.0s/"),
+            ("", "", "qRPK7m23GJusamGpoGLby/"),
+        ] {
+            let crypted = md5_crypt(password.as_bytes(), salt.as_bytes());
+            assert_eq!(
+                String::from_utf8(crypted).unwrap(),
+                hash,
+                "{password:?} {salt:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sha256_crypt_known_answers() {
+        for (password, salt, rounds, hash) in [
+            (
+                "Hello world!",
+                "saltstring",
+                SHA_ROUNDS_DEFAULT,
+                "5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+            ),
+            (
+                "Hello world!",
+                "saltstringsaltstring",
+                10000,
+                "3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA",
+            ),
+            (
+                "This is just a test",
+                "toolongsaltstring",
+                5000,
+                "Un/5jzAHMgOGZ5.mWJpuVolil07guHPvOW8mGRcvxa5",
+            ),
+        ] {
+            let crypted = sha256_crypt(password.as_bytes(), salt.as_bytes(), rounds);
+            assert_eq!(
+                String::from_utf8(crypted).unwrap(),
+                hash,
+                "{password:?} {salt:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sha512_crypt_known_answers() {
+        for (password, salt, rounds, hash) in [
+            (
+                "Hello world!",
+                "saltstring",
+                SHA_ROUNDS_DEFAULT,
+                "svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1",
+            ),
+            (
+                "Hello world!",
+                "saltstringsaltstring",
+                10000,
+                "OW1/O6BYHV6BcXZu8QVeXbDWra3Oeqh0sbHbbMCVNSnCM/UrjmM0Dp8vOuZeHBy/YTBmSK6H9qs/y3RnOaw5v.",
+            ),
+            (
+                "a very much longer text to encrypt.  This one even stretches over morethan one line.",
+                "anotherlongsaltstring",
+                1400,
+                "POfYwTEok97VWcjxIiSOjiykti.o/pQs.wPvMxQ6Fm7I6IoYN3CmLs66x9t0oSwbtEW7o7UmJEiDwGqd8p4ur1",
+            ),
+        ] {
+            let crypted = sha512_crypt(password.as_bytes(), salt.as_bytes(), rounds);
+            assert_eq!(String::from_utf8(crypted).unwrap(), hash, "{password:?} {salt:?}");
+        }
+    }
+}
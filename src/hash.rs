@@ -0,0 +1,528 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use std::{
+    any::Any,
+    collections::HashMap,
+    convert::Infallible,
+    fmt,
+    hash::{self, BuildHasherDefault, Hasher},
+    ops::Deref,
+};
+
+use clap::{builder::PossibleValue, ValueEnum};
+use eyre::{bail, eyre, Result};
+
+use crate::{chain::Chain, Entry};
+use md4::Md4;
+use sha2::Digest;
+
+mod jwt;
+mod kdf;
+mod netntlm;
+mod raw;
+mod unix;
+
+pub type Hash = Vec<u8>;
+pub type Salt = Vec<u8>;
+pub type Digests = HashMap<Hash, Vec<Entry>, BuildHasherDefault<DigestHasher>>;
+
+// Digests are already uniformly distributed, so their leading bytes are as
+// good a hash as any and cost far less than SipHash on every candidate.
+#[derive(Default)]
+pub struct DigestHasher(u64);
+
+impl Hasher for DigestHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut leading = [0; 8];
+        let n = bytes.len().min(8);
+        leading[..n].copy_from_slice(&bytes[..n]);
+        self.0 = self.0.rotate_left(5) ^ u64::from_le_bytes(leading);
+    }
+}
+
+pub trait HashAlgorithm: Sync {
+    // Everything besides the password that goes into a digest, targets sharing
+    // the same parameters are checked together.
+    type Params: Eq + hash::Hash + Send + Sync + 'static;
+
+    fn name(&self) -> &'static str;
+
+    fn digest_len(&self) -> usize;
+
+    fn slow(&self) -> bool {
+        false
+    }
+
+    // Formats that carry their own parameters are recognised on every line,
+    // whichever mode was asked for.
+    fn self_describing(&self) -> bool {
+        false
+    }
+
+    fn recognise(&self, _line: &str) -> Option<Result<(Self::Params, Hash)>> {
+        None
+    }
+
+    fn parse(&self, _line: &str, _salt_first: bool) -> Result<(Self::Params, Hash)> {
+        bail!("not a {} hash", self.name());
+    }
+
+    // Typical parameters for benchmarking, none for modes that only resolve
+    // to an algorithm once hashes are read.
+    fn sample(&self) -> Option<Self::Params>;
+
+    fn hash(&self, password: &[u8], params: &Self::Params, out: &mut Hash);
+
+    // Finds the targets a candidate cracks among those sharing `params`,
+    // `digest` is the worker's scratch buffer.
+    fn verify<'t>(
+        &self,
+        password: &[u8],
+        params: &Self::Params,
+        digests: &'t Digests,
+        digest: &mut Hash,
+    ) -> Option<&'t [Entry]> {
+        self.hash(password, params, digest);
+        digests.get(digest).map(Vec::as_slice)
+    }
+}
+
+trait AnyParams: Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn eq_any(&self, other: &dyn AnyParams) -> bool;
+
+    fn hash_any(&self, state: &mut dyn Hasher);
+}
+
+impl<P: Eq + hash::Hash + Send + Sync + 'static> AnyParams for P {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_any(&self, other: &dyn AnyParams) -> bool {
+        other.as_any().downcast_ref() == Some(self)
+    }
+
+    fn hash_any(&self, mut state: &mut dyn Hasher) {
+        hash::Hash::hash(self, &mut state);
+    }
+}
+
+// The parameters of whichever algorithm a target belongs to.
+pub struct Params(Box<dyn AnyParams>);
+
+impl Params {
+    fn new(params: impl AnyParams) -> Self {
+        Params(Box::new(params))
+    }
+}
+
+impl PartialEq for Params {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_any(&*other.0)
+    }
+}
+
+impl Eq for Params {}
+
+impl hash::Hash for Params {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash_any(state);
+    }
+}
+
+// Algorithms as the registry sees them, with their parameters erased.
+pub trait AnyAlgorithm: Sync {
+    fn name(&self) -> &'static str;
+
+    fn digest_len(&self) -> usize;
+
+    fn slow(&self) -> bool;
+
+    fn self_describing(&self) -> bool;
+
+    fn recognise(&self, line: &str) -> Option<Result<(Params, Hash)>>;
+
+    fn parse(&self, line: &str, salt_first: bool) -> Result<(Params, Hash)>;
+
+    fn sample(&self) -> Option<Params>;
+
+    fn verify<'t>(
+        &self,
+        password: &[u8],
+        params: &Params,
+        digests: &'t Digests,
+        digest: &mut Hash,
+    ) -> Option<&'t [Entry]>;
+}
+
+impl<A: HashAlgorithm> AnyAlgorithm for A {
+    fn name(&self) -> &'static str {
+        HashAlgorithm::name(self)
+    }
+
+    fn digest_len(&self) -> usize {
+        HashAlgorithm::digest_len(self)
+    }
+
+    fn slow(&self) -> bool {
+        HashAlgorithm::slow(self)
+    }
+
+    fn self_describing(&self) -> bool {
+        HashAlgorithm::self_describing(self)
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(Params, Hash)>> {
+        let parsed = HashAlgorithm::recognise(self, line)?;
+        Some(parsed.map(|(params, hash)| (Params::new(params), hash)))
+    }
+
+    fn parse(&self, line: &str, salt_first: bool) -> Result<(Params, Hash)> {
+        let (params, hash) = HashAlgorithm::parse(self, line, salt_first)?;
+        Ok((Params::new(params), hash))
+    }
+
+    fn sample(&self) -> Option<Params> {
+        HashAlgorithm::sample(self).map(Params::new)
+    }
+
+    fn verify<'t>(
+        &self,
+        password: &[u8],
+        params: &Params,
+        digests: &'t Digests,
+        digest: &mut Hash,
+    ) -> Option<&'t [Entry]> {
+        let params = params
+            .0
+            .as_any()
+            .downcast_ref()
+            .expect("targets are keyed by mode and its params");
+        HashAlgorithm::verify(self, password, params, digests, digest)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct HashMode(&'static dyn AnyAlgorithm);
+
+impl HashMode {
+    pub const AUTO: HashMode = HashMode(&Auto);
+    pub const CHAIN: HashMode = HashMode(&ChainMode);
+}
+
+impl Deref for HashMode {
+    type Target = dyn AnyAlgorithm;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl PartialEq for HashMode {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+impl Eq for HashMode {}
+
+impl hash::Hash for HashMode {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.name().hash(state);
+    }
+}
+
+impl fmt::Display for HashMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl ValueEnum for HashMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        MODES
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.name()))
+    }
+}
+
+pub const MODES: &[HashMode] = &[
+    HashMode::AUTO,
+    HashMode(&raw::SHA1),
+    HashMode(&raw::SHA1_PASS_SALT),
+    HashMode(&raw::SHA1_SALT_PASS),
+    HashMode(&raw::SHA224),
+    HashMode(&raw::SHA256),
+    HashMode(&raw::SHA256_PASS_SALT),
+    HashMode(&raw::SHA256_SALT_PASS),
+    HashMode(&raw::SHA512),
+    HashMode(&raw::SHA512_PASS_SALT),
+    HashMode(&raw::SHA512_SALT_PASS),
+    HashMode(&raw::SHA384),
+    HashMode(&raw::SHA512_256),
+    HashMode(&raw::MD5),
+    HashMode(&raw::MD5_PASS_SALT),
+    HashMode(&raw::MD5_SALT_PASS),
+    HashMode(&kdf::Bcrypt),
+    HashMode(&kdf::ARGON2I),
+    HashMode(&kdf::ARGON2D),
+    HashMode(&kdf::ARGON2ID),
+    HashMode(&kdf::PBKDF2_SHA256),
+    HashMode(&kdf::PBKDF2_SHA512),
+    HashMode(&kdf::Scrypt),
+    HashMode(&unix::Md5Crypt),
+    HashMode(&unix::SHA256_CRYPT),
+    HashMode(&unix::SHA512_CRYPT),
+    HashMode(&raw::Ntlm),
+    HashMode(&netntlm::NetNtlmV1),
+    HashMode(&netntlm::NetNtlmV2),
+    HashMode(&raw::HMAC_SHA256_PASS),
+    HashMode(&raw::HMAC_SHA256_SALT),
+    HashMode(&raw::HMAC_SHA512_PASS),
+    HashMode(&raw::HMAC_SHA512_SALT),
+    HashMode(&raw::HMAC_MD5_PASS),
+    HashMode(&raw::HMAC_MD5_SALT),
+    HashMode(&jwt::JWT_HS256),
+    HashMode(&jwt::JWT_HS384),
+    HashMode(&jwt::JWT_HS512),
+    HashMode::CHAIN,
+];
+
+struct Auto;
+
+impl HashAlgorithm for Auto {
+    type Params = Infallible;
+
+    fn name(&self) -> &'static str {
+        "auto"
+    }
+
+    fn digest_len(&self) -> usize {
+        0
+    }
+
+    fn sample(&self) -> Option<Infallible> {
+        None
+    }
+
+    // Auto is resolved to other modes when reading hashes.
+    fn hash(&self, _: &[u8], params: &Infallible, _: &mut Hash) {
+        match *params {}
+    }
+}
+
+struct ChainMode;
+
+#[derive(PartialEq, Eq, Hash)]
+struct ChainParams {
+    chain: Chain,
+    salt: Salt,
+}
+
+impl HashAlgorithm for ChainMode {
+    type Params = ChainParams;
+
+    fn name(&self) -> &'static str {
+        "chain"
+    }
+
+    fn digest_len(&self) -> usize {
+        0
+    }
+
+    fn sample(&self) -> Option<ChainParams> {
+        None
+    }
+
+    fn hash(&self, password: &[u8], params: &ChainParams, out: &mut Hash) {
+        *out = params.chain.eval(password, &params.salt);
+    }
+}
+
+fn decode(encoded: &str) -> Result<Hash> {
+    hex::decode(encoded).or_else(|_| {
+        BASE64
+            .decode(encoded)
+            .map_err(|_| eyre!("digest is neither hex nor base64"))
+    })
+}
+
+fn split_salt(line: &str, salt_first: bool) -> Option<(&str, &str)> {
+    if salt_first {
+        line.rsplit_once(':').map(|(salt, hash)| (hash, salt))
+    } else {
+        line.split_once(':')
+    }
+}
+
+fn parse_salted(line: &str, salt_first: bool) -> Result<(Salt, Hash)> {
+    let (hash, salt) = split_salt(line, salt_first).ok_or_else(|| eyre!("missing salt"))?;
+    Ok((salt.as_bytes().to_vec(), decode(hash)?))
+}
+
+fn strip_prefixes<'a>(line: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+    prefixes.iter().find_map(|prefix| line.strip_prefix(prefix))
+}
+
+fn detect(line: &str, salt_first: bool) -> Result<Vec<(HashMode, Params, Hash)>> {
+    let found = MODES
+        .iter()
+        .filter(|mode| !mode.self_describing())
+        .filter_map(|&mode| {
+            let (params, hash) = mode.parse(line, salt_first).ok()?;
+            (hash.len() == mode.digest_len()).then_some((mode, params, hash))
+        })
+        .collect::<Vec<_>>();
+    if found.is_empty() {
+        bail!("unrecognised hash format");
+    }
+    Ok(found)
+}
+
+pub fn split_shadow(line: &str) -> Option<(&str, &str)> {
+    let fields: Vec<&str> = line.split(':').collect();
+    if fields.len() != 9 {
+        return None;
+    }
+    // A leading `!` only marks the account as locked, the hash is intact.
+    Some((fields[0], fields[1].trim_start_matches('!')))
+}
+
+pub fn parse_target(
+    line: &str,
+    hash_mode: HashMode,
+    chain: Option<&Chain>,
+    salt_first: bool,
+) -> Result<Vec<(HashMode, Params, Hash)>> {
+    let recognised = MODES
+        .iter()
+        .find_map(|&mode| Some((mode, mode.recognise(line)?)));
+    if let Some((mode, parsed)) = recognised {
+        let (params, hash) = parsed?;
+        if hash_mode != HashMode::AUTO && hash_mode != mode {
+            bail!("{mode} hash given for mode {hash_mode}");
+        }
+        return Ok(vec![(mode, params, hash)]);
+    }
+    if hash_mode == HashMode::AUTO {
+        return detect(line, salt_first);
+    }
+    if let Some(chain) = chain {
+        let (salt, hash) = match chain.salted() {
+            true => parse_salted(line, salt_first)?,
+            false => (Salt::new(), decode(line)?),
+        };
+        check_len(&hash, chain.digest_len(), hash_mode)?;
+        let params = Params::new(ChainParams {
+            chain: chain.clone(),
+            salt,
+        });
+        return Ok(vec![(hash_mode, params, hash)]);
+    }
+    let (params, hash) = hash_mode.parse(line, salt_first)?;
+    check_len(&hash, hash_mode.digest_len(), hash_mode)?;
+    Ok(vec![(hash_mode, params, hash)])
+}
+
+fn check_len(hash: &Hash, len: usize, mode: HashMode) -> Result<()> {
+    if hash.len() != len {
+        bail!(
+            "{}-byte digest given for mode {mode}, expected {len}",
+            hash.len()
+        );
+    }
+    Ok(())
+}
+
+fn digest<D: Digest>(parts: &[&[u8]], out: &mut Hash) {
+    let mut hasher = D::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    out.clear();
+    out.extend_from_slice(&hasher.finalize());
+}
+
+fn md5_digest(parts: &[&[u8]], out: &mut Hash) {
+    let mut context = md5::Context::new();
+    for part in parts {
+        context.consume(part);
+    }
+    out.clear();
+    out.extend_from_slice(&*context.compute());
+}
+
+// Pads are sized for SHA-512's 128 byte blocks and 64 byte digests, the
+// largest in use, so nothing is allocated per candidate.
+fn hmac(hash: fn(&[&[u8]], &mut Hash), block: usize, key: &[u8], message: &[u8], out: &mut Hash) {
+    let mut ipad = [0x36; 128];
+    let mut opad = [0x5c; 128];
+    match key.len() > block {
+        true => hash(&[key], out),
+        false => {
+            out.clear();
+            out.extend_from_slice(key);
+        }
+    }
+    for (i, k) in out.iter().enumerate() {
+        ipad[i] ^= k;
+        opad[i] ^= k;
+    }
+    hash(&[&ipad[..block], message], out);
+    let mut inner = [0; 64];
+    let inner = &mut inner[..out.len()];
+    inner.copy_from_slice(out);
+    hash(&[&opad[..block], inner], out);
+}
+
+// MD4 of the UTF-16LE password, ASCII is widened a chunk at a time on the
+// stack.
+fn ntlm(password: &[u8], out: &mut Hash) {
+    let mut hasher = Md4::new();
+    if password.is_ascii() {
+        for chunk in password.chunks(64) {
+            let mut wide = [0; 128];
+            for (i, &c) in chunk.iter().enumerate() {
+                wide[2 * i] = c;
+            }
+            hasher.update(&wide[..2 * chunk.len()]);
+        }
+    } else {
+        hasher.update(utf16le(password));
+    }
+    out.clear();
+    out.extend_from_slice(&hasher.finalize());
+}
+
+// Like hashcat, bytes that are not UTF-8 are taken as ISO-8859-1 rather than
+// all collapsing into U+FFFD.
+fn utf16le(data: &[u8]) -> Vec<u8> {
+    match std::str::from_utf8(data) {
+        Ok(text) => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        Err(_) => data.iter().flat_map(|&c| [c, 0]).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ntlm_widens_latin1() {
+        let nt = |password: &[u8]| {
+            let mut out = Hash::new();
+            ntlm(password, &mut out);
+            hex::encode(out)
+        };
+        assert_eq!(nt(b"password"), "8846f7eaee8fb117ad06bdd830b7586c");
+        assert_eq!(nt(b"caf\xe9"), nt("café".as_bytes()));
+        assert_ne!(nt(b"caf\xe9"), nt(b"caf\xe8"));
+    }
+}
@@ -0,0 +1,79 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use eyre::{eyre, Result};
+use sha2::{Sha256, Sha384, Sha512};
+
+use super::{digest, hmac, Hash, HashAlgorithm, Salt};
+
+pub struct Jwt {
+    name: &'static str,
+    alg: &'static str,
+    len: usize,
+    digest: fn(&[&[u8]], &mut Hash),
+    block: usize,
+}
+
+pub const JWT_HS256: Jwt = Jwt {
+    name: "jwt-hs256",
+    alg: "HS256",
+    len: 32,
+    digest: digest::<Sha256>,
+    block: 64,
+};
+pub const JWT_HS384: Jwt = Jwt {
+    name: "jwt-hs384",
+    alg: "HS384",
+    len: 48,
+    digest: digest::<Sha384>,
+    block: 128,
+};
+pub const JWT_HS512: Jwt = Jwt {
+    name: "jwt-hs512",
+    alg: "HS512",
+    len: 64,
+    digest: digest::<Sha512>,
+    block: 128,
+};
+
+impl HashAlgorithm for Jwt {
+    type Params = Salt;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        self.len
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    // The secret signs `header.payload`, which is kept verbatim as the salt.
+    fn recognise(&self, line: &str) -> Option<Result<(Salt, Hash)>> {
+        let [header, _, signature] = line.split('.').collect::<Vec<_>>()[..] else {
+            return None;
+        };
+        let header = URL_SAFE_NO_PAD.decode(header).ok()?;
+        let header: serde_json::Value = serde_json::from_slice(&header).ok()?;
+        if header.get("alg")?.as_str()? != self.alg {
+            return None;
+        }
+        let parsed = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| eyre!("invalid base64url JWT signature"))
+            .map(|signature| {
+                let (message, _) = line.rsplit_once('.').unwrap();
+                (message.as_bytes().to_vec(), signature)
+            });
+        Some(parsed)
+    }
+
+    fn sample(&self) -> Option<Salt> {
+        Some(b"saltsalt".to_vec())
+    }
+
+    fn hash(&self, password: &[u8], message: &Salt, out: &mut Hash) {
+        hmac(self.digest, self.block, password, message, out);
+    }
+}
@@ -0,0 +1,451 @@
+use base64::{
+    alphabet,
+    engine::{
+        general_purpose::{
+            GeneralPurpose, GeneralPurposeConfig, STANDARD as BASE64, STANDARD_NO_PAD,
+        },
+        DecodePaddingMode,
+    },
+    Engine,
+};
+use std::collections::HashMap;
+
+use eyre::{bail, eyre, Result, WrapErr};
+use sha2::{Sha256, Sha512};
+
+use super::{strip_prefixes, Hash, HashAlgorithm, Salt};
+
+const BCRYPT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::BCRYPT,
+    GeneralPurposeConfig::new()
+        .with_encode_padding(false)
+        .with_decode_padding_mode(DecodePaddingMode::RequireNone)
+        .with_decode_allow_trailing_bits(true),
+);
+
+pub struct Bcrypt;
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct BcryptParams {
+    cost: u32,
+    salt: [u8; 16],
+}
+
+pub struct Argon2 {
+    name: &'static str,
+    prefix: &'static str,
+    algorithm: argon2::Algorithm,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct Argon2Params {
+    version: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    len: usize,
+    salt: Salt,
+}
+
+pub struct Pbkdf2 {
+    name: &'static str,
+    len: usize,
+    // passlib/PHC and Django spellings
+    prefixes: [&'static str; 2],
+    prf: fn(&[u8], &[u8], u32, &mut [u8]),
+}
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct Pbkdf2Params {
+    rounds: u32,
+    len: usize,
+    salt: Salt,
+}
+
+pub struct Scrypt;
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    len: usize,
+    salt: Salt,
+}
+
+pub const ARGON2I: Argon2 = Argon2 {
+    name: "argon2i",
+    prefix: "$argon2i$",
+    algorithm: argon2::Algorithm::Argon2i,
+};
+pub const ARGON2D: Argon2 = Argon2 {
+    name: "argon2d",
+    prefix: "$argon2d$",
+    algorithm: argon2::Algorithm::Argon2d,
+};
+pub const ARGON2ID: Argon2 = Argon2 {
+    name: "argon2id",
+    prefix: "$argon2id$",
+    algorithm: argon2::Algorithm::Argon2id,
+};
+pub const PBKDF2_SHA256: Pbkdf2 = Pbkdf2 {
+    name: "pbkdf2-sha256",
+    len: 32,
+    prefixes: ["$pbkdf2-sha256$", "pbkdf2_sha256$"],
+    prf: pbkdf2::pbkdf2_hmac::<Sha256>,
+};
+pub const PBKDF2_SHA512: Pbkdf2 = Pbkdf2 {
+    name: "pbkdf2-sha512",
+    len: 64,
+    prefixes: ["$pbkdf2-sha512$", "pbkdf2_sha512$"],
+    prf: pbkdf2::pbkdf2_hmac::<Sha512>,
+};
+
+fn phc_params(params: &str) -> Result<HashMap<&str, u32>> {
+    params
+        .split(',')
+        .map(|param| {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| eyre!("invalid parameter {param:?}"))?;
+            let value = value.parse().wrap_err_with(|| format!("invalid {name}"))?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn phc_param(params: &HashMap<&str, u32>, name: &str) -> Result<u32> {
+    params
+        .get(name)
+        .copied()
+        .ok_or_else(|| eyre!("missing {name}"))
+}
+
+fn parse_bcrypt(settings: &str) -> Result<(BcryptParams, Hash)> {
+    let (cost, encoded) = settings
+        .split_once('$')
+        .ok_or_else(|| eyre!("missing cost"))?;
+    let cost = cost.parse().wrap_err("invalid cost")?;
+    if !(4..=31).contains(&cost) {
+        bail!("cost {cost} out of range");
+    }
+    if encoded.len() != 53 || !encoded.is_ascii() {
+        bail!("expected 53 characters of salt and digest");
+    }
+    let salt = BCRYPT_BASE64
+        .decode(&encoded[..22])
+        .wrap_err("invalid salt")?
+        .try_into()
+        .map_err(|_| eyre!("invalid salt length"))?;
+    let hash = BCRYPT_BASE64
+        .decode(&encoded[22..])
+        .wrap_err("invalid digest")?;
+    Ok((BcryptParams { cost, salt }, hash))
+}
+
+impl HashAlgorithm for Bcrypt {
+    type Params = BcryptParams;
+
+    fn name(&self) -> &'static str {
+        "bcrypt"
+    }
+
+    fn digest_len(&self) -> usize {
+        23
+    }
+
+    fn slow(&self) -> bool {
+        true
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(BcryptParams, Hash)>> {
+        strip_prefixes(line, &["$2a$", "$2b$", "$2y$"]).map(parse_bcrypt)
+    }
+
+    fn sample(&self) -> Option<BcryptParams> {
+        Some(BcryptParams {
+            cost: 10,
+            salt: *b"saltsaltsaltsalt",
+        })
+    }
+
+    fn hash(&self, password: &[u8], params: &BcryptParams, out: &mut Hash) {
+        let mut key = password.to_vec();
+        key.push(0);
+        key.truncate(72);
+        out.clear();
+        out.extend_from_slice(&bcrypt::bcrypt(params.cost, params.salt, &key)[..23]);
+    }
+}
+
+impl Argon2 {
+    fn hasher(&self, params: &Argon2Params) -> Result<argon2::Argon2<'static>> {
+        let version = argon2::Version::try_from(params.version).map_err(|e| eyre!("{e}"))?;
+        let Argon2Params {
+            m_cost,
+            t_cost,
+            p_cost,
+            len,
+            ..
+        } = *params;
+        let params =
+            argon2::Params::new(m_cost, t_cost, p_cost, Some(len)).map_err(|e| eyre!("{e}"))?;
+        Ok(argon2::Argon2::new(self.algorithm, version, params))
+    }
+
+    fn parse(&self, settings: &str) -> Result<(Argon2Params, Hash)> {
+        let mut fields: Vec<&str> = settings.split('$').collect();
+        let version = match fields.first().and_then(|v| v.strip_prefix("v=")) {
+            Some(version) => {
+                fields.remove(0);
+                version.parse().wrap_err("invalid version")?
+            }
+            None => 0x10,
+        };
+        let [costs, salt, hash] = fields[..] else {
+            bail!("expected parameters, salt and digest");
+        };
+        let costs = phc_params(costs)?;
+        let salt = STANDARD_NO_PAD.decode(salt).wrap_err("invalid salt")?;
+        if salt.len() < argon2::MIN_SALT_LEN {
+            bail!("salt shorter than {} bytes", argon2::MIN_SALT_LEN);
+        }
+        let hash = STANDARD_NO_PAD.decode(hash).wrap_err("invalid digest")?;
+        let params = Argon2Params {
+            version,
+            m_cost: phc_param(&costs, "m")?,
+            t_cost: phc_param(&costs, "t")?,
+            p_cost: phc_param(&costs, "p")?,
+            len: hash.len(),
+            salt,
+        };
+        // Build the hasher once so bad parameters are reported here rather than
+        // while cracking.
+        self.hasher(&params)?;
+        Ok((params, hash))
+    }
+}
+
+impl HashAlgorithm for Argon2 {
+    type Params = Argon2Params;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        32
+    }
+
+    fn slow(&self) -> bool {
+        true
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(Argon2Params, Hash)>> {
+        Some(self.parse(line.strip_prefix(self.prefix)?))
+    }
+
+    fn sample(&self) -> Option<Argon2Params> {
+        Some(Argon2Params {
+            version: 0x13,
+            m_cost: argon2::Params::DEFAULT_M_COST,
+            t_cost: argon2::Params::DEFAULT_T_COST,
+            p_cost: argon2::Params::DEFAULT_P_COST,
+            len: argon2::Params::DEFAULT_OUTPUT_LEN,
+            salt: b"saltsaltsaltsalt".to_vec(),
+        })
+    }
+
+    fn hash(&self, password: &[u8], params: &Argon2Params, out: &mut Hash) {
+        let hasher = self
+            .hasher(params)
+            .expect("argon2 params are checked when parsed");
+        out.resize(params.len, 0);
+        hasher
+            .hash_password_into(password, &params.salt, out)
+            .expect("argon2 params are checked when parsed");
+    }
+}
+
+fn pbkdf2_params(rounds: u32, salt: Salt, hash: &Hash) -> Result<Pbkdf2Params> {
+    if rounds == 0 {
+        bail!("rounds must be positive");
+    }
+    Ok(Pbkdf2Params {
+        rounds,
+        len: hash.len(),
+        salt,
+    })
+}
+
+// passlib writes `$pbkdf2-sha256$rounds$salt$hash` with its "adapted" base64
+// (`.` instead of `+`), the PHC form uses `i=rounds[,l=len]`.
+fn parse_pbkdf2(settings: &str) -> Result<(Pbkdf2Params, Hash)> {
+    let [rounds, salt, hash] = settings.split('$').collect::<Vec<_>>()[..] else {
+        bail!("expected rounds, salt and digest");
+    };
+    let rounds = match rounds.parse() {
+        Ok(rounds) => rounds,
+        Err(_) => phc_param(&phc_params(rounds)?, "i")?,
+    };
+    let ab64 = |s: &str| STANDARD_NO_PAD.decode(s.replace('.', "+"));
+    let salt = ab64(salt).wrap_err("invalid salt")?;
+    let hash = ab64(hash).wrap_err("invalid digest")?;
+    Ok((pbkdf2_params(rounds, salt, &hash)?, hash))
+}
+
+fn parse_django_pbkdf2(settings: &str) -> Result<(Pbkdf2Params, Hash)> {
+    let [rounds, salt, hash] = settings.split('$').collect::<Vec<_>>()[..] else {
+        bail!("expected rounds, salt and digest");
+    };
+    let rounds = rounds.parse().wrap_err("invalid rounds")?;
+    let hash = BASE64.decode(hash).wrap_err("invalid digest")?;
+    let salt = salt.as_bytes().to_vec();
+    Ok((pbkdf2_params(rounds, salt, &hash)?, hash))
+}
+
+impl HashAlgorithm for Pbkdf2 {
+    type Params = Pbkdf2Params;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        self.len
+    }
+
+    fn slow(&self) -> bool {
+        true
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(Pbkdf2Params, Hash)>> {
+        let [phc, django] = self.prefixes;
+        if let Some(settings) = line.strip_prefix(phc) {
+            return Some(parse_pbkdf2(settings));
+        }
+        Some(parse_django_pbkdf2(line.strip_prefix(django)?))
+    }
+
+    fn sample(&self) -> Option<Pbkdf2Params> {
+        Some(Pbkdf2Params {
+            rounds: 10000,
+            len: self.len,
+            salt: b"saltsaltsaltsalt".to_vec(),
+        })
+    }
+
+    fn hash(&self, password: &[u8], params: &Pbkdf2Params, out: &mut Hash) {
+        out.resize(params.len, 0);
+        (self.prf)(password, &params.salt, params.rounds, out);
+    }
+}
+
+fn scrypt_cost(params: &ScryptParams) -> Result<scrypt::Params> {
+    let ScryptParams {
+        log_n, r, p, len, ..
+    } = *params;
+    scrypt::Params::new(log_n, r, p, len).map_err(|e| eyre!("{e}"))
+}
+
+fn scrypt_params(log_n: u8, r: u32, p: u32, salt: Salt, hash: &Hash) -> Result<ScryptParams> {
+    let params = ScryptParams {
+        log_n,
+        r,
+        p,
+        len: hash.len(),
+        salt,
+    };
+    scrypt_cost(&params)?;
+    Ok(params)
+}
+
+fn parse_scrypt(settings: &str) -> Result<(ScryptParams, Hash)> {
+    let [costs, salt, hash] = settings.split('$').collect::<Vec<_>>()[..] else {
+        bail!("expected parameters, salt and digest");
+    };
+    let costs = phc_params(costs)?;
+    let log_n = phc_param(&costs, "ln")?.try_into().wrap_err("invalid ln")?;
+    let salt = STANDARD_NO_PAD.decode(salt).wrap_err("invalid salt")?;
+    let hash = STANDARD_NO_PAD.decode(hash).wrap_err("invalid digest")?;
+    let params = scrypt_params(
+        log_n,
+        phc_param(&costs, "r")?,
+        phc_param(&costs, "p")?,
+        salt,
+        &hash,
+    )?;
+    Ok((params, hash))
+}
+
+fn parse_django_scrypt(settings: &str) -> Result<(ScryptParams, Hash)> {
+    let [salt, n, r, p, hash] = settings.split('$').collect::<Vec<_>>()[..] else {
+        bail!("expected salt, N, r, p and digest");
+    };
+    let n: u64 = n.parse().wrap_err("invalid N")?;
+    if !n.is_power_of_two() {
+        bail!("N must be a power of two");
+    }
+    let log_n = n.trailing_zeros() as u8;
+    let r = r.parse().wrap_err("invalid r")?;
+    let p = p.parse().wrap_err("invalid p")?;
+    let hash = BASE64.decode(hash).wrap_err("invalid digest")?;
+    let salt = salt.as_bytes().to_vec();
+    Ok((scrypt_params(log_n, r, p, salt, &hash)?, hash))
+}
+
+impl HashAlgorithm for Scrypt {
+    type Params = ScryptParams;
+
+    fn name(&self) -> &'static str {
+        "scrypt"
+    }
+
+    fn digest_len(&self) -> usize {
+        32
+    }
+
+    fn slow(&self) -> bool {
+        true
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(ScryptParams, Hash)>> {
+        if let Some(settings) = line.strip_prefix("$scrypt$") {
+            return Some(parse_scrypt(settings));
+        }
+        Some(parse_django_scrypt(line.strip_prefix("scrypt$")?))
+    }
+
+    fn sample(&self) -> Option<ScryptParams> {
+        Some(ScryptParams {
+            log_n: 14,
+            r: 8,
+            p: 1,
+            len: 32,
+            salt: b"saltsaltsaltsalt".to_vec(),
+        })
+    }
+
+    fn hash(&self, password: &[u8], params: &ScryptParams, out: &mut Hash) {
+        let cost = scrypt_cost(params).expect("scrypt params are checked when parsed");
+        out.resize(params.len, 0);
+        scrypt::scrypt(password, &params.salt, &cost, out)
+            .expect("scrypt params are checked when parsed");
+    }
+}
@@ -0,0 +1,140 @@
+use des::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use eyre::Result;
+
+use super::{hmac, md5_digest, ntlm, utf16le, Hash, HashAlgorithm};
+
+pub struct NetNtlmV1;
+
+pub struct NetNtlmV2;
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct NetNtlmV2Params {
+    identity: Vec<u8>,
+    challenge: Vec<u8>,
+}
+
+// NetNTLM responses are captured as `user::domain:...`, v1 as
+// `lm_response:nt_response:challenge` and v2 as
+// `server_challenge:nt_proof:blob`.
+fn fields(line: &str) -> Option<[&str; 5]> {
+    let [user, "", domain, a, b, c] = line.split(':').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    Some([user, domain, a, b, c])
+}
+
+fn des_encrypt(key: &[u8], block: &[u8]) -> [u8; 8] {
+    let key = [
+        key[0],
+        key[0] << 7 | key[1] >> 1,
+        key[1] << 6 | key[2] >> 2,
+        key[2] << 5 | key[3] >> 3,
+        key[3] << 4 | key[4] >> 4,
+        key[4] << 3 | key[5] >> 5,
+        key[5] << 2 | key[6] >> 6,
+        key[6] << 1,
+    ];
+    let mut block = GenericArray::clone_from_slice(block);
+    des::Des::new(&key.into()).encrypt_block(&mut block);
+    block.into()
+}
+
+impl HashAlgorithm for NetNtlmV1 {
+    type Params = [u8; 8];
+
+    fn name(&self) -> &'static str {
+        "net-ntlm-v1"
+    }
+
+    fn digest_len(&self) -> usize {
+        24
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<([u8; 8], Hash)>> {
+        let [_, _, lm, nt, server_challenge] = fields(line)?;
+        if lm.len() != 48 || nt.len() != 48 || server_challenge.len() != 16 {
+            return None;
+        }
+        let parsed = (|| {
+            let (lm, nt) = (hex::decode(lm)?, hex::decode(nt)?);
+            let mut challenge = [0; 8];
+            hex::decode_to_slice(server_challenge, &mut challenge)?;
+            // With extended session security the LM field carries the client
+            // challenge, which is mixed into the one the server sent.
+            if lm[8..].iter().all(|&x| x == 0) {
+                let mut mixed = Hash::new();
+                md5_digest(&[&challenge, &lm[..8]], &mut mixed);
+                challenge.copy_from_slice(&mixed[..8]);
+            }
+            Ok((challenge, nt))
+        })();
+        Some(parsed)
+    }
+
+    fn sample(&self) -> Option<[u8; 8]> {
+        Some(*b"saltsalt")
+    }
+
+    fn hash(&self, password: &[u8], challenge: &[u8; 8], out: &mut Hash) {
+        let mut key = [0; 21];
+        ntlm(password, out);
+        key[..16].copy_from_slice(out);
+        out.clear();
+        for key in key.chunks(7) {
+            out.extend_from_slice(&des_encrypt(key, challenge));
+        }
+    }
+}
+
+impl HashAlgorithm for NetNtlmV2 {
+    type Params = NetNtlmV2Params;
+
+    fn name(&self) -> &'static str {
+        "net-ntlm-v2"
+    }
+
+    fn digest_len(&self) -> usize {
+        16
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(NetNtlmV2Params, Hash)>> {
+        let [user, domain, challenge, proof, blob] = fields(line)?;
+        if challenge.len() != 16 || proof.len() != 32 {
+            return None;
+        }
+        let parsed = (|| {
+            let identity = utf16le(format!("{}{domain}", user.to_uppercase()).as_bytes());
+            let challenge = [hex::decode(challenge)?, hex::decode(blob)?].concat();
+            let params = NetNtlmV2Params {
+                identity,
+                challenge,
+            };
+            Ok((params, hex::decode(proof)?))
+        })();
+        Some(parsed)
+    }
+
+    fn sample(&self) -> Option<NetNtlmV2Params> {
+        Some(NetNtlmV2Params {
+            identity: utf16le(b"USERDOMAIN"),
+            challenge: [&b"saltsalt"[..], &[0; 32]].concat(),
+        })
+    }
+
+    fn hash(&self, password: &[u8], params: &NetNtlmV2Params, out: &mut Hash) {
+        let mut key = [0; 16];
+        ntlm(password, out);
+        key.copy_from_slice(out);
+        hmac(md5_digest, 64, &key, &params.identity, out);
+        key.copy_from_slice(out);
+        hmac(md5_digest, 64, &key, &params.challenge, out);
+    }
+}
@@ -0,0 +1,316 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use eyre::{bail, eyre, Result};
+use sha1::Sha1;
+use sha2::{Sha224, Sha256, Sha384, Sha512, Sha512_256};
+
+use super::{decode, digest, hmac, md5_digest, ntlm, parse_salted, Hash, HashAlgorithm, Salt};
+
+type Digest = fn(&[&[u8]], &mut Hash);
+
+enum Order {
+    PassSalt,
+    SaltPass,
+}
+
+enum Key {
+    Pass,
+    Salt,
+}
+
+pub struct Plain {
+    name: &'static str,
+    len: usize,
+    digest: Digest,
+    ldap: Option<&'static str>,
+}
+
+pub struct Salted {
+    name: &'static str,
+    len: usize,
+    digest: Digest,
+    order: Order,
+    ldap: Option<&'static str>,
+}
+
+pub struct Hmac {
+    name: &'static str,
+    len: usize,
+    digest: Digest,
+    block: usize,
+    key: Key,
+}
+
+pub struct Ntlm;
+
+pub const SHA1: Plain = Plain {
+    name: "sha1",
+    len: 20,
+    digest: digest::<Sha1>,
+    ldap: Some("{SHA}"),
+};
+pub const SHA1_PASS_SALT: Salted = Salted {
+    name: "sha1-pass-salt",
+    len: 20,
+    digest: digest::<Sha1>,
+    order: Order::PassSalt,
+    ldap: Some("{SSHA}"),
+};
+pub const SHA1_SALT_PASS: Salted = Salted {
+    name: "sha1-salt-pass",
+    len: 20,
+    digest: digest::<Sha1>,
+    order: Order::SaltPass,
+    ldap: None,
+};
+pub const SHA224: Plain = Plain {
+    name: "sha224",
+    len: 28,
+    digest: digest::<Sha224>,
+    ldap: None,
+};
+pub const SHA256: Plain = Plain {
+    name: "sha256",
+    len: 32,
+    digest: digest::<Sha256>,
+    ldap: None,
+};
+pub const SHA256_PASS_SALT: Salted = Salted {
+    name: "sha256-pass-salt",
+    len: 32,
+    digest: digest::<Sha256>,
+    order: Order::PassSalt,
+    ldap: Some("{SSHA256}"),
+};
+pub const SHA256_SALT_PASS: Salted = Salted {
+    name: "sha256-salt-pass",
+    len: 32,
+    digest: digest::<Sha256>,
+    order: Order::SaltPass,
+    ldap: None,
+};
+pub const SHA512: Plain = Plain {
+    name: "sha512",
+    len: 64,
+    digest: digest::<Sha512>,
+    ldap: None,
+};
+pub const SHA512_PASS_SALT: Salted = Salted {
+    name: "sha512-pass-salt",
+    len: 64,
+    digest: digest::<Sha512>,
+    order: Order::PassSalt,
+    ldap: Some("{SSHA512}"),
+};
+pub const SHA512_SALT_PASS: Salted = Salted {
+    name: "sha512-salt-pass",
+    len: 64,
+    digest: digest::<Sha512>,
+    order: Order::SaltPass,
+    ldap: None,
+};
+pub const SHA384: Plain = Plain {
+    name: "sha384",
+    len: 48,
+    digest: digest::<Sha384>,
+    ldap: None,
+};
+pub const SHA512_256: Plain = Plain {
+    name: "sha512-256",
+    len: 32,
+    digest: digest::<Sha512_256>,
+    ldap: None,
+};
+pub const MD5: Plain = Plain {
+    name: "md5",
+    len: 16,
+    digest: md5_digest,
+    ldap: None,
+};
+pub const MD5_PASS_SALT: Salted = Salted {
+    name: "md5-pass-salt",
+    len: 16,
+    digest: md5_digest,
+    order: Order::PassSalt,
+    ldap: None,
+};
+pub const MD5_SALT_PASS: Salted = Salted {
+    name: "md5-salt-pass",
+    len: 16,
+    digest: md5_digest,
+    order: Order::SaltPass,
+    ldap: None,
+};
+pub const HMAC_SHA256_PASS: Hmac = Hmac {
+    name: "hmac-sha256-pass",
+    len: 32,
+    digest: digest::<Sha256>,
+    block: 64,
+    key: Key::Pass,
+};
+pub const HMAC_SHA256_SALT: Hmac = Hmac {
+    name: "hmac-sha256-salt",
+    len: 32,
+    digest: digest::<Sha256>,
+    block: 64,
+    key: Key::Salt,
+};
+pub const HMAC_SHA512_PASS: Hmac = Hmac {
+    name: "hmac-sha512-pass",
+    len: 64,
+    digest: digest::<Sha512>,
+    block: 128,
+    key: Key::Pass,
+};
+pub const HMAC_SHA512_SALT: Hmac = Hmac {
+    name: "hmac-sha512-salt",
+    len: 64,
+    digest: digest::<Sha512>,
+    block: 128,
+    key: Key::Salt,
+};
+pub const HMAC_MD5_PASS: Hmac = Hmac {
+    name: "hmac-md5-pass",
+    len: 16,
+    digest: md5_digest,
+    block: 64,
+    key: Key::Pass,
+};
+pub const HMAC_MD5_SALT: Hmac = Hmac {
+    name: "hmac-md5-salt",
+    len: 16,
+    digest: md5_digest,
+    block: 64,
+    key: Key::Salt,
+};
+
+// LDAP stores `{SCHEME}base64(digest || salt)`.
+fn parse_ldap(
+    line: &str,
+    prefix: Option<&str>,
+    len: usize,
+    salted: bool,
+) -> Option<Result<(Salt, Hash)>> {
+    let encoded = line.strip_prefix(prefix?)?;
+    let parsed = BASE64
+        .decode(encoded)
+        .map_err(|_| eyre!("invalid base64 after {}", prefix.unwrap()))
+        .and_then(|mut hash| {
+            if hash.len() < len || (hash.len() > len && !salted) {
+                bail!("invalid digest length for {}", prefix.unwrap());
+            }
+            let salt = hash.split_off(len);
+            Ok((salt, hash))
+        });
+    Some(parsed)
+}
+
+impl HashAlgorithm for Plain {
+    type Params = ();
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        self.len
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<((), Hash)>> {
+        let parsed = parse_ldap(line, self.ldap, self.len, false)?;
+        Some(parsed.map(|(_, hash)| ((), hash)))
+    }
+
+    fn parse(&self, line: &str, _: bool) -> Result<((), Hash)> {
+        Ok(((), decode(line)?))
+    }
+
+    fn sample(&self) -> Option<()> {
+        Some(())
+    }
+
+    fn hash(&self, password: &[u8], _: &(), out: &mut Hash) {
+        (self.digest)(&[password], out);
+    }
+}
+
+impl HashAlgorithm for Salted {
+    type Params = Salt;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        self.len
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(Salt, Hash)>> {
+        parse_ldap(line, self.ldap, self.len, true)
+    }
+
+    fn parse(&self, line: &str, salt_first: bool) -> Result<(Salt, Hash)> {
+        parse_salted(line, salt_first)
+    }
+
+    fn sample(&self) -> Option<Salt> {
+        Some(b"saltsalt".to_vec())
+    }
+
+    fn hash(&self, password: &[u8], salt: &Salt, out: &mut Hash) {
+        match self.order {
+            Order::PassSalt => (self.digest)(&[password, salt], out),
+            Order::SaltPass => (self.digest)(&[salt, password], out),
+        }
+    }
+}
+
+impl HashAlgorithm for Hmac {
+    type Params = Salt;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        self.len
+    }
+
+    fn parse(&self, line: &str, salt_first: bool) -> Result<(Salt, Hash)> {
+        parse_salted(line, salt_first)
+    }
+
+    fn sample(&self) -> Option<Salt> {
+        Some(b"saltsalt".to_vec())
+    }
+
+    fn hash(&self, password: &[u8], salt: &Salt, out: &mut Hash) {
+        match self.key {
+            Key::Pass => hmac(self.digest, self.block, password, salt, out),
+            Key::Salt => hmac(self.digest, self.block, salt, password, out),
+        }
+    }
+}
+
+impl HashAlgorithm for Ntlm {
+    type Params = ();
+
+    fn name(&self) -> &'static str {
+        "ntlm"
+    }
+
+    fn digest_len(&self) -> usize {
+        16
+    }
+
+    fn parse(&self, line: &str, _: bool) -> Result<((), Hash)> {
+        Ok(((), decode(line)?))
+    }
+
+    fn sample(&self) -> Option<()> {
+        Some(())
+    }
+
+    fn hash(&self, password: &[u8], _: &(), out: &mut Hash) {
+        ntlm(password, out);
+    }
+}
@@ -0,0 +1,133 @@
+use eyre::{bail, eyre, Result, WrapErr};
+
+use super::{Hash, HashAlgorithm, Salt};
+use crate::crypt;
+
+pub struct Md5Crypt;
+
+pub struct ShaCrypt {
+    name: &'static str,
+    prefix: &'static str,
+    len: usize,
+    crypt: fn(&[u8], &[u8], u32) -> Vec<u8>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+pub struct ShaCryptParams {
+    rounds: u32,
+    salt: Salt,
+}
+
+pub const SHA256_CRYPT: ShaCrypt = ShaCrypt {
+    name: "sha256-crypt",
+    prefix: "$5$",
+    len: 43,
+    crypt: crypt::sha256_crypt,
+};
+pub const SHA512_CRYPT: ShaCrypt = ShaCrypt {
+    name: "sha512-crypt",
+    prefix: "$6$",
+    len: 86,
+    crypt: crypt::sha512_crypt,
+};
+
+impl HashAlgorithm for Md5Crypt {
+    type Params = Salt;
+
+    fn name(&self) -> &'static str {
+        "md5-crypt"
+    }
+
+    fn digest_len(&self) -> usize {
+        22
+    }
+
+    fn slow(&self) -> bool {
+        true
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(Salt, Hash)>> {
+        let settings = line.strip_prefix("$1$")?;
+        let parsed = (|| {
+            let (salt, hash) = settings
+                .split_once('$')
+                .ok_or_else(|| eyre!("expected salt and digest"))?;
+            if hash.len() != 22 {
+                bail!("expected a 22 character digest");
+            }
+            let salt = salt.as_bytes()[..salt.len().min(8)].to_vec();
+            Ok((salt, hash.as_bytes().to_vec()))
+        })();
+        Some(parsed)
+    }
+
+    fn sample(&self) -> Option<Salt> {
+        Some(b"saltsalt".to_vec())
+    }
+
+    fn hash(&self, password: &[u8], salt: &Salt, out: &mut Hash) {
+        *out = crypt::md5_crypt(password, salt);
+    }
+}
+
+impl ShaCrypt {
+    fn parse(&self, settings: &str) -> Result<(ShaCryptParams, Hash)> {
+        let (rounds, settings) = match settings.strip_prefix("rounds=") {
+            Some(settings) => {
+                let (rounds, settings) = settings
+                    .split_once('$')
+                    .ok_or_else(|| eyre!("expected salt after rounds"))?;
+                let rounds: u32 = rounds.parse().wrap_err("invalid rounds")?;
+                (rounds.clamp(1000, 999_999_999), settings)
+            }
+            None => (crypt::SHA_ROUNDS_DEFAULT, settings),
+        };
+        let (salt, hash) = settings
+            .split_once('$')
+            .ok_or_else(|| eyre!("expected salt and digest"))?;
+        if hash.len() != self.len {
+            bail!("expected a {} character digest", self.len);
+        }
+        let salt = salt.as_bytes()[..salt.len().min(16)].to_vec();
+        Ok((ShaCryptParams { rounds, salt }, hash.as_bytes().to_vec()))
+    }
+}
+
+impl HashAlgorithm for ShaCrypt {
+    type Params = ShaCryptParams;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn digest_len(&self) -> usize {
+        self.len
+    }
+
+    fn slow(&self) -> bool {
+        true
+    }
+
+    fn self_describing(&self) -> bool {
+        true
+    }
+
+    fn recognise(&self, line: &str) -> Option<Result<(ShaCryptParams, Hash)>> {
+        Some(self.parse(line.strip_prefix(self.prefix)?))
+    }
+
+    fn sample(&self) -> Option<ShaCryptParams> {
+        Some(ShaCryptParams {
+            rounds: crypt::SHA_ROUNDS_DEFAULT,
+            salt: b"saltsaltsaltsalt".to_vec(),
+        })
+    }
+
+    fn hash(&self, password: &[u8], params: &ShaCryptParams, out: &mut Hash) {
+        *out = (self.crypt)(password, &params.salt, params.rounds);
+    }
+}
@@ -0,0 +1,83 @@
+use eyre::{bail, eyre, Result};
+
+type Charset = Vec<u8>;
+
+pub struct Keyspace {
+    segments: Vec<Vec<Charset>>,
+    sizes: Vec<u64>,
+    len: u64,
+}
+
+impl Keyspace {
+    pub fn new(segments: Vec<Vec<Charset>>) -> Result<Self> {
+        let mut sizes = Vec::with_capacity(segments.len());
+        let mut len = 0u64;
+        for positions in &segments {
+            let mut size = 1u64;
+            for charset in positions {
+                if charset.is_empty() {
+                    bail!("empty charset in keyspace");
+                }
+                size = size
+                    .checked_mul(charset.len() as u64)
+                    .ok_or_else(|| eyre!("keyspace too large"))?;
+            }
+            len = len
+                .checked_add(size)
+                .ok_or_else(|| eyre!("keyspace too large"))?;
+            sizes.push(size);
+        }
+        Ok(Self {
+            segments,
+            sizes,
+            len,
+        })
+    }
+
+    pub fn bruteforce(charset: &[u8], min_len: usize, max_len: usize) -> Result<Self> {
+        if min_len > max_len {
+            bail!("min length {min_len} is greater than max length {max_len}");
+        }
+        let mut charset = charset.to_vec();
+        charset.sort_unstable();
+        charset.dedup();
+        let segments = (min_len..=max_len)
+            .map(|len| vec![charset.clone(); len])
+            .collect();
+        Self::new(segments)
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    // Appends the candidate so callers can build words in a reused buffer.
+    pub fn write(&self, mut index: u64, out: &mut Vec<u8>) {
+        for (positions, &size) in self.segments.iter().zip(&self.sizes) {
+            if index >= size {
+                index -= size;
+                continue;
+            }
+            let start = out.len();
+            out.resize(start + positions.len(), 0);
+            for (c, charset) in out[start..].iter_mut().zip(positions).rev() {
+                let radix = charset.len() as u64;
+                *c = charset[(index % radix) as usize];
+                index /= radix;
+            }
+            return;
+        }
+        panic!("keyspace index out of range");
+    }
+
+    pub fn ranges(&self, n: usize) -> Vec<(u64, u64)> {
+        let len = self.len as u128;
+        (0..n as u128)
+            .map(|i| {
+                let start = (len * i / n as u128) as u64;
+                let end = (len * (i + 1) / n as u128) as u64;
+                (start, end)
+            })
+            .collect()
+    }
+}
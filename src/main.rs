@@ -1,7 +1,14 @@
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
     io::SeekFrom,
-    sync::{atomic::AtomicBool, Arc},
-    time::Instant,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 use tokio::{
@@ -9,129 +16,770 @@ use tokio::{
     io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, BufReader},
 };
 
-use tokio_stream::{wrappers::LinesStream, Stream};
+use chain::Chain;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use eyre::{bail, Result, WrapErr};
+use hash::{parse_target, split_shadow, Digests, HashMode, Params, MODES};
+use keyspace::Keyspace;
+use potfile::Potfile;
+use rules::{Rule, Rules};
+use session::Session;
+use status::{Event, Progress};
+use worker::{visit_words, Source};
 
-use clap::{Parser, Subcommand, ValueEnum};
-use eyre::Result;
-use futures::{future::try_join_all, StreamExt};
-use sha2::{Digest, Sha256, Sha512};
+mod benchmark;
+mod chain;
+mod crypt;
+mod hash;
+mod keyspace;
+mod mask;
+mod potfile;
+mod rules;
+mod session;
+mod status;
+mod worker;
 
-type Hash = Vec<u8>;
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
 
-#[derive(Clone, Copy, ValueEnum)]
-enum HashMode {
-    Sha256,
-    Sha512,
-    MD5,
+type Targets = HashMap<(HashMode, Params), Digests>;
+
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Entry {
+    user: Option<String>,
+    hash: String,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.user {
+            Some(user) => write!(f, "{user}:{}", self.hash),
+            None => write!(f, "{}", self.hash),
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct MaskArgs {
+    mask: String,
+    #[arg(short = '1', long)]
+    custom_charset1: Option<String>,
+    #[arg(short = '2', long)]
+    custom_charset2: Option<String>,
+    #[arg(short = '3', long)]
+    custom_charset3: Option<String>,
+    #[arg(short = '4', long)]
+    custom_charset4: Option<String>,
+}
+
+impl MaskArgs {
+    fn keyspace(self) -> Result<Keyspace> {
+        let custom = [
+            self.custom_charset1,
+            self.custom_charset2,
+            self.custom_charset3,
+            self.custom_charset4,
+        ];
+        let positions = mask::parse_mask(&self.mask, &custom)?;
+        Keyspace::new(vec![positions])
+    }
 }
 
 #[derive(Subcommand)]
 enum CrackMode {
-    Dictionary { path: String },
-    Bruteforce,
+    Dictionary {
+        path: String,
+        #[arg(short, long)]
+        rules: Option<String>,
+    },
+    Bruteforce {
+        #[arg(short, long, default_value = "abcdefghijklmnopqrstuvwxyz0123456789")]
+        charset: String,
+        #[arg(long, default_value_t = 1)]
+        min_len: usize,
+        #[arg(long, default_value_t = 6)]
+        max_len: usize,
+    },
+    Mask {
+        #[command(flatten)]
+        mask: MaskArgs,
+    },
+    HybridWordMask {
+        path: String,
+        #[command(flatten)]
+        mask: MaskArgs,
+    },
+    HybridMaskWord {
+        #[command(flatten)]
+        mask: MaskArgs,
+        path: String,
+    },
+    Combinator {
+        left: String,
+        right: String,
+        #[arg(short, long, default_value = "")]
+        separator: String,
+        #[arg(short = 'j', long)]
+        rule_left: Option<String>,
+        #[arg(short = 'k', long)]
+        rule_right: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum Command {
+    Benchmark {
+        #[arg(short, long, value_enum)]
+        mode: Option<HashMode>,
+    },
+    #[command(flatten)]
+    Crack(CrackMode),
 }
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-#[command(propagate_version = true)]
+#[command(propagate_version = true, subcommand_negates_reqs = true)]
 struct Args {
-    hash_path: String,
-    #[arg(value_enum)]
-    hash_mode: HashMode,
+    #[arg(long, exclusive = true)]
+    list_modes: bool,
+    #[arg(required_unless_present_any = ["list_modes", "restore"])]
+    hash_path: Option<String>,
+    #[arg(value_enum, required_unless_present_any = ["list_modes", "restore"])]
+    hash_mode: Option<HashMode>,
+    #[arg(long, value_parser = Chain::parse)]
+    chain: Option<Chain>,
+    #[arg(long)]
+    salt_first: bool,
+    #[arg(long, default_value = "scream.pot")]
+    potfile: String,
+    #[arg(long, conflicts_with = "show")]
+    no_potfile: bool,
+    #[arg(long)]
+    show: bool,
+    #[arg(long, conflicts_with = "show")]
+    session: Option<String>,
+    #[arg(long, requires = "session", conflicts_with_all = ["hash_path", "hash_mode"])]
+    restore: bool,
     #[command(subcommand)]
-    crack_mode: CrackMode,
+    command: Option<Command>,
 }
 
-async fn read_hash(path: &str) -> Result<Hash> {
-    let f = File::open(path).await?;
-    let mut r = BufReader::new(f);
-    let mut hash = String::new();
-    r.read_to_string(&mut hash).await?;
-    Ok(hex::decode(hash.trim())?)
+enum Outcome {
+    Cracked,
+    Exhausted,
+    Interrupted(i32),
 }
 
-async fn read_wordlist(path: &str) -> Result<Vec<impl Stream<Item = String>>> {
-    let f = File::open(path).await?;
-    let s = f.metadata().await?.len() as usize;
-    let n = num_cpus::get();
-    println!("{n} CPUs");
-    let spn = s / n;
-    let mut streams = Vec::with_capacity(n);
-    for i in 0..n {
-        let f = File::open(path).await?;
-        let mut r = BufReader::with_capacity(spn, f);
-        r.seek(SeekFrom::Start((i * spn) as u64)).await?;
-        streams.push(LinesStream::new(r.lines()).map(|l| l.unwrap()));
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        match outcome {
+            Outcome::Cracked => ExitCode::SUCCESS,
+            Outcome::Exhausted => ExitCode::from(1),
+            Outcome::Interrupted(signal) => ExitCode::from(128 + signal as u8),
+        }
     }
-    Ok(streams)
 }
 
-#[inline]
-fn gen_hash(data: &[u8], hash_mode: HashMode) -> Hash {
-    match hash_mode {
-        HashMode::Sha256 => {
-            let mut hasher = Sha256::new();
-            hasher.update(data);
-            hasher.finalize().to_vec()
+async fn read_hashes(
+    path: &str,
+    hash_mode: HashMode,
+    chain: Option<&Chain>,
+    salt_first: bool,
+) -> Result<Targets> {
+    let f = File::open(path).await?;
+    let mut r = BufReader::new(f);
+    let mut hashes = String::new();
+    r.read_to_string(&mut hashes).await?;
+    let mut targets = Targets::new();
+    let mut ambiguous = BTreeMap::<Vec<String>, usize>::new();
+    let mut locked = 0;
+    let mut unsupported = 0;
+    for (i, line) in hashes.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (user, line) = match split_shadow(line) {
+            Some((_, hash)) if !hash.starts_with('$') => {
+                locked += 1;
+                continue;
+            }
+            Some((user, hash)) => (Some(user.to_string()), hash),
+            None => (None, line),
+        };
+        let parsed = match parse_target(line, hash_mode, chain, salt_first) {
+            Ok(parsed) => parsed,
+            // Shadow files mix schemes, so other schemes are not an error.
+            Err(_) if user.is_some() => {
+                unsupported += 1;
+                continue;
+            }
+            Err(e) => return Err(e).wrap_err_with(|| format!("{path}:{}: invalid hash", i + 1)),
+        };
+        if parsed.len() > 1 {
+            let modes = parsed.iter().map(|(mode, _, _)| mode.to_string()).collect();
+            *ambiguous.entry(modes).or_default() += 1;
         }
-        HashMode::Sha512 => {
-            let mut hasher = Sha512::new();
-            hasher.update(data);
-            hasher.finalize().to_vec()
+        let entry = Entry {
+            user,
+            hash: line.to_string(),
+        };
+        for (mode, params, hash) in parsed {
+            let hashes = targets.entry((mode, params)).or_default();
+            hashes.entry(hash).or_default().push(entry.clone());
         }
-        HashMode::MD5 => md5::compute(data).to_vec(),
+    }
+    if targets.is_empty() {
+        bail!("no hashes found in {path}");
+    }
+    if locked > 0 {
+        println!("Skipped {locked} shadow entries without a password hash");
+    }
+    if unsupported > 0 {
+        println!("Skipped {unsupported} shadow entries with unsupported or other-mode hashes");
+    }
+    for (modes, count) in ambiguous {
+        println!(
+            "{count} hashes could be any of {}, trying all",
+            modes.join(", ")
+        );
+    }
+    Ok(targets)
+}
+
+async fn partition_wordlist(path: &str, n: usize) -> Result<Vec<(u64, u64)>> {
+    let mut r = BufReader::new(File::open(path).await?);
+    let s = r.get_ref().metadata().await?.len();
+    let mut bounds = vec![0];
+    let mut skipped = Vec::new();
+    for i in 1..n as u64 {
+        let nominal = s * i / n as u64;
+        let prev = *bounds.last().unwrap();
+        let start = if nominal <= prev {
+            prev
+        } else {
+            // A chunk owns every line that starts inside it, so move the
+            // boundary past the line that straddles the nominal offset.
+            r.seek(SeekFrom::Start(nominal - 1)).await?;
+            skipped.clear();
+            nominal - 1 + r.read_until(b'\n', &mut skipped).await? as u64
+        };
+        bounds.push(start);
+    }
+    bounds.push(s);
+    Ok(bounds.windows(2).map(|w| (w[0], w[1])).collect())
+}
+
+async fn read_words(path: &str) -> Result<Vec<Vec<u8>>> {
+    let words = tokio::fs::read(path).await?;
+    if words.is_empty() {
+        bail!("no words in {path}");
+    }
+    let words = words.strip_suffix(b"\n").unwrap_or(&words);
+    Ok(words
+        .split(|&b| b == b'\n')
+        .map(|w| w.strip_suffix(b"\r").unwrap_or(w).to_vec())
+        .collect())
+}
+
+struct Job {
+    targets: Targets,
+    potfile: Potfile,
+    session: Option<Session>,
+}
+
+impl Job {
+    fn resumed(&self) -> Option<Vec<(u64, u64)>> {
+        self.session.as_ref().and_then(Session::workers)
+    }
+
+    async fn wordlist_ranges(&self, path: &str) -> Result<Vec<(u64, u64)>> {
+        let ranges = match self.resumed() {
+            Some(ranges) => ranges,
+            None => partition_wordlist(path, num_cpus::get()).await?,
+        };
+        println!("{} CPUs", ranges.len());
+        Ok(ranges)
     }
 }
 
-async fn crack_with_wordlist(hash: Hash, wordlist_path: &str, hash_mode: HashMode) -> Result<()> {
-    let wordlist = read_wordlist(wordlist_path).await?;
-    let mut tasks = Vec::new();
-    let found = Arc::new(AtomicBool::new(false));
+fn crack(job: Job, ranges: Vec<(u64, u64)>, sources: Vec<Source>) -> Result<Outcome> {
+    let Job {
+        targets,
+        potfile,
+        mut session,
+    } = job;
+    let total = targets
+        .values()
+        .flat_map(HashMap::values)
+        .flatten()
+        .collect::<HashSet<_>>()
+        .len();
+    let cracked = Mutex::new(HashSet::<Entry>::new());
+    let done = AtomicBool::new(false);
+    let progress = Progress::new(ranges, total);
     let crack_time = Instant::now();
-    for mut chunk in wordlist {
-        let found = found.clone();
-        let hash = hash.clone();
-        let task = tokio::spawn(async move {
-            loop {
-                if found.load(std::sync::atomic::Ordering::Relaxed) {
-                    break;
+    let on_hit = |mode: HashMode, entry: &Entry, password: &[u8]| {
+        let mut cracked = cracked.lock().unwrap();
+        if !cracked.insert(entry.clone()) {
+            return Ok(());
+        }
+        println!(
+            "{entry} ({mode}) --- {:<16} [{:>14?}]",
+            String::from_utf8_lossy(password),
+            crack_time.elapsed()
+        );
+        potfile.record(&entry.hash, password)?;
+        if cracked.len() == total {
+            done.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    };
+    let (events, received) = mpsc::channel();
+    let listener = status::listen(events.clone())?;
+    let (results, monitor) = std::thread::scope(|s| {
+        let (progress, cracked, done, session) = (&progress, &cracked, &done, &mut session);
+        let monitor = s.spawn(move || {
+            // A failed checkpoint stops the workers rather than letting them
+            // run on with nothing saved.
+            let mut checkpoint = || match session.as_mut() {
+                Some(session) => {
+                    let cracked = cracked.lock().unwrap().clone();
+                    session
+                        .save(
+                            progress.workers(),
+                            cracked.into_iter().map(|entry| entry.hash),
+                        )
+                        .inspect_err(|_| done.store(true, Ordering::Relaxed))
                 }
-                if let Some(password) = chunk.next().await {
-                    if gen_hash(password.as_bytes(), hash_mode) == *hash {
-                        println!(
-                            "{} --- {password:<16} [{:>14?}]",
-                            hex::encode(&*hash),
-                            crack_time.elapsed()
-                        );
-                        found.fetch_or(true, std::sync::atomic::Ordering::Relaxed);
-                        break;
+                None => Ok(()),
+            };
+            let mut next_status = Instant::now() + STATUS_INTERVAL;
+            let mut next_checkpoint = Instant::now() + CHECKPOINT_INTERVAL;
+            loop {
+                let timeout = next_status
+                    .min(next_checkpoint)
+                    .saturating_duration_since(Instant::now());
+                let requested = match received.recv_timeout(timeout) {
+                    Ok(Event::Status) => true,
+                    Ok(Event::Interrupt(signal)) => {
+                        done.store(true, Ordering::Relaxed);
+                        eprintln!("{}", progress.status(cracked.lock().unwrap().len()));
+                        checkpoint()?;
+                        return Ok(Some(signal));
                     }
+                    Ok(Event::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) => false,
+                };
+                let now = Instant::now();
+                if requested || now >= next_status {
+                    eprintln!("{}", progress.status(cracked.lock().unwrap().len()));
+                    next_status = now + STATUS_INTERVAL;
+                }
+                if now >= next_checkpoint {
+                    checkpoint()?;
+                    next_checkpoint = now + CHECKPOINT_INTERVAL;
                 }
             }
+            Ok::<_, eyre::Report>(None)
         });
-        tasks.push(task);
+        let results = worker::run(&targets, sources, progress, done, &on_hit);
+        let _ = events.send(Event::Stop);
+        (results, monitor.join().unwrap())
+    });
+    drop(listener);
+    let interrupted = monitor?;
+    results?;
+    if let Some(signal) = interrupted {
+        return Ok(Outcome::Interrupted(signal));
+    }
+    if let Some(session) = &session {
+        session.remove()?;
     }
-    try_join_all(tasks).await?;
     let crack_time = crack_time.elapsed();
-    if !found.load(std::sync::atomic::Ordering::Relaxed) {
-        println!("No password found for the given hash (search took {crack_time:6?}):");
+    let recovered = cracked.into_inner().unwrap().len();
+    if recovered == 0 {
+        println!("No password found for the given hashes (search took {crack_time:6?})");
+    } else {
+        println!("Recovered {recovered}/{total} hashes (search took {crack_time:6?})");
+    }
+    if recovered == total {
+        Ok(Outcome::Cracked)
+    } else {
+        Ok(Outcome::Exhausted)
+    }
+}
+
+async fn crack_with_wordlist(
+    job: Job,
+    wordlist_path: &str,
+    rules: Option<Rules>,
+) -> Result<Outcome> {
+    let ranges = job.wordlist_ranges(wordlist_path).await?;
+    let rules = rules.map(Arc::new);
+    if let Some(rules) = &rules {
+        println!("{} rules", rules.len());
+    }
+    let sources = ranges
+        .iter()
+        .map(|&range| {
+            let path = wordlist_path.to_string();
+            match rules.clone() {
+                Some(rules) => worker::source(move |visit| {
+                    visit_words(&path, range, |position, word| {
+                        rules.apply(word).iter().all(|w| visit.push(position, w))
+                    })
+                }),
+                None => worker::source(move |visit| {
+                    visit_words(&path, range, |position, word| visit.push(position, word))
+                }),
+            }
+        })
+        .collect();
+    crack(job, ranges, sources)
+}
+
+async fn crack_with_keyspace(job: Job, keyspace: Keyspace) -> Result<Outcome> {
+    let ranges = job
+        .resumed()
+        .unwrap_or_else(|| keyspace.ranges(num_cpus::get()));
+    println!("{} CPUs, {} candidates", ranges.len(), keyspace.len());
+    let keyspace = Arc::new(keyspace);
+    let sources = ranges
+        .iter()
+        .map(|&(start, end)| {
+            let keyspace = keyspace.clone();
+            worker::source(move |visit| {
+                let mut candidate = Vec::new();
+                for index in start..end {
+                    candidate.clear();
+                    keyspace.write(index, &mut candidate);
+                    if !visit.push(index, &candidate) {
+                        break;
+                    }
+                }
+                Ok(())
+            })
+        })
+        .collect();
+    crack(job, ranges, sources)
+}
+
+async fn crack_with_hybrid(
+    job: Job,
+    wordlist_path: &str,
+    keyspace: Keyspace,
+    append: bool,
+) -> Result<Outcome> {
+    let ranges = job.wordlist_ranges(wordlist_path).await?;
+    println!("{} mask candidates per word", keyspace.len());
+    let keyspace = Arc::new(keyspace);
+    let sources = ranges
+        .iter()
+        .map(|&range| {
+            let path = wordlist_path.to_string();
+            let keyspace = keyspace.clone();
+            worker::source(move |visit| {
+                let mut candidate = Vec::new();
+                visit_words(&path, range, |position, word| {
+                    (0..keyspace.len()).all(|i| {
+                        candidate.clear();
+                        if append {
+                            candidate.extend_from_slice(word);
+                            keyspace.write(i, &mut candidate);
+                        } else {
+                            keyspace.write(i, &mut candidate);
+                            candidate.extend_from_slice(word);
+                        }
+                        visit.push(position, &candidate)
+                    })
+                })
+            })
+        })
+        .collect();
+    crack(job, ranges, sources)
+}
+
+async fn crack_with_combinator(
+    job: Job,
+    left_path: &str,
+    right_path: &str,
+    separator: Vec<u8>,
+    rule_left: Option<Rule>,
+    rule_right: Option<Rule>,
+) -> Result<Outcome> {
+    let ranges = job.wordlist_ranges(left_path).await?;
+    let mut right = read_words(right_path).await?;
+    if let Some(rule) = rule_right {
+        right = right.iter().filter_map(|w| rule.apply(w)).collect();
+    }
+    println!("{} right-hand words", right.len());
+    let right = Arc::new(right);
+    let rule_left = Arc::new(rule_left);
+    let separator = Arc::new(separator);
+    let sources = ranges
+        .iter()
+        .map(|&range| {
+            let path = left_path.to_string();
+            let right = right.clone();
+            let rule_left = rule_left.clone();
+            let separator = separator.clone();
+            worker::source(move |visit| {
+                let mut candidate = Vec::new();
+                visit_words(&path, range, |position, word| {
+                    let applied = rule_left.as_ref().as_ref().map(|rule| rule.apply(word));
+                    let word = match &applied {
+                        Some(Some(word)) => word,
+                        Some(None) => return true,
+                        None => word,
+                    };
+                    right.iter().all(|right| {
+                        candidate.clear();
+                        candidate.extend_from_slice(word);
+                        candidate.extend_from_slice(&separator);
+                        candidate.extend_from_slice(right);
+                        visit.push(position, &candidate)
+                    })
+                })
+            })
+        })
+        .collect();
+    crack(job, ranges, sources)
+}
+
+fn show_cracked(targets: &Targets, potfile: &Potfile) -> Outcome {
+    let entries = targets
+        .values()
+        .flat_map(HashMap::values)
+        .flatten()
+        .collect::<BTreeSet<_>>();
+    let mut recovered = 0;
+    for entry in &entries {
+        if let Some(plain) = potfile.show(&entry.hash) {
+            println!("{entry}:{plain}");
+            recovered += 1;
+        }
+    }
+    if recovered == entries.len() {
+        Outcome::Cracked
+    } else {
+        Outcome::Exhausted
+    }
+}
+
+// Drops every hash that is already known to be cracked and returns how many
+// were dropped.
+fn skip_cracked(targets: &mut Targets, known: impl Fn(&str) -> bool) -> usize {
+    let mut skipped = HashSet::new();
+    for hashes in targets.values_mut() {
+        for entries in hashes.values_mut() {
+            entries.retain(|entry| {
+                if !known(&entry.hash) {
+                    return true;
+                }
+                skipped.insert(entry.clone());
+                false
+            });
+        }
+        hashes.retain(|_, entries| !entries.is_empty());
+    }
+    targets.retain(|_, hashes| !hashes.is_empty());
+    skipped.len()
+}
+
+async fn run(mut args: Args) -> Result<Outcome> {
+    if args.list_modes {
+        for mode in MODES {
+            println!("{mode}");
+        }
+        return Ok(Outcome::Cracked);
+    }
+    if let Some(Command::Benchmark { mode }) = args.command {
+        let attack = args.hash_path.is_some() || args.hash_mode.is_some();
+        if attack || args.session.is_some() {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "benchmark takes no hash path, hash mode or session",
+                )
+                .exit();
+        }
+        benchmark::benchmark(mode).await?;
+        return Ok(Outcome::Cracked);
+    }
+    let session = match (args.session.clone(), args.restore) {
+        (Some(name), true) => {
+            let session = Session::load(&name).await?;
+            args = Args::try_parse_from(session.args())?;
+            println!("Restoring session {name}");
+            Some(session)
+        }
+        (Some(name), false) => Some(Session::new(&name, std::env::args().collect())?),
+        (None, _) => None,
+    };
+    let (Some(hash_path), Some(hash_mode)) = (args.hash_path, args.hash_mode) else {
+        Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "a hash path and hash mode are required",
+            )
+            .exit();
+    };
+    if hash_mode == HashMode::CHAIN && args.chain.is_none() {
+        bail!("the chain mode needs a --chain expression");
+    }
+    if hash_mode != HashMode::CHAIN && args.chain.is_some() {
+        bail!("--chain only applies to the chain mode");
+    }
+    let mut targets =
+        read_hashes(&hash_path, hash_mode, args.chain.as_ref(), args.salt_first).await?;
+    let potfile = if args.no_potfile {
+        Potfile::disabled()
+    } else {
+        let hashes = targets
+            .values()
+            .flat_map(HashMap::values)
+            .flatten()
+            .map(|entry| entry.hash.as_str())
+            .collect();
+        Potfile::load(&args.potfile, &hashes).await?
+    };
+    if args.show {
+        return Ok(show_cracked(&targets, &potfile));
+    }
+    let Some(Command::Crack(crack_mode)) = args.command else {
+        Args::command()
+            .error(ErrorKind::MissingSubcommand, "a crack mode is required")
+            .exit();
+    };
+    let skipped = skip_cracked(&mut targets, |hash| potfile.contains(hash));
+    if skipped > 0 {
+        println!("Skipped {skipped} hashes already in the potfile");
+    }
+    if let Some(session) = &session {
+        let skipped = skip_cracked(&mut targets, |hash| session.cracked().contains(hash));
+        if skipped > 0 {
+            println!("Skipped {skipped} hashes cracked earlier in this session");
+        }
+    }
+    if targets.is_empty() {
+        println!("All hashes have already been cracked");
+        if let Some(session) = &session {
+            session.remove()?;
+        }
+        return Ok(Outcome::Cracked);
+    }
+    let job = Job {
+        targets,
+        potfile,
+        session,
+    };
+
+    match crack_mode {
+        CrackMode::Dictionary { path, rules } => {
+            let rules = match rules {
+                Some(path) => Some(Rules::load(&path).await?),
+                None => None,
+            };
+            crack_with_wordlist(job, &path, rules).await
+        }
+        CrackMode::Bruteforce {
+            charset,
+            min_len,
+            max_len,
+        } => {
+            let keyspace = Keyspace::bruteforce(charset.as_bytes(), min_len, max_len)?;
+            crack_with_keyspace(job, keyspace).await
+        }
+        CrackMode::Mask { mask } => crack_with_keyspace(job, mask.keyspace()?).await,
+        CrackMode::HybridWordMask { path, mask } => {
+            crack_with_hybrid(job, &path, mask.keyspace()?, true).await
+        }
+        CrackMode::HybridMaskWord { mask, path } => {
+            crack_with_hybrid(job, &path, mask.keyspace()?, false).await
+        }
+        CrackMode::Combinator {
+            left,
+            right,
+            separator,
+            rule_left,
+            rule_right,
+        } => {
+            let rule_left = rule_left.as_deref().map(Rule::parse).transpose()?;
+            let rule_right = rule_right.as_deref().map(Rule::parse).transpose()?;
+            crack_with_combinator(
+                job,
+                &left,
+                &right,
+                separator.into_bytes(),
+                rule_left,
+                rule_right,
+            )
+            .await
+        }
     }
-    Ok(())
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let args = Args::parse();
-    let hash = read_hash(&args.hash_path).await?;
-    // let hash = Arc::new(hash);
+    match run(args).await {
+        Ok(outcome) => outcome.into(),
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(2)
+        }
+    }
+}
 
-    // TODO:
-    // 1. Extract to function and add good multi hash support
-    // 2. Extract to 2 separate functions, one for single hash, one for multi hash
-    match args.crack_mode {
-        CrackMode::Dictionary { path } => crack_with_wordlist(hash, &path, args.hash_mode).await?,
-        CrackMode::Bruteforce => todo!("implement bruteforce"),
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use worker::BLOCK_SIZE;
+
+    async fn visit_partitions(content: &str, n: usize) -> Vec<String> {
+        static FILES: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "scream-test-{}-{}",
+            std::process::id(),
+            FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let path = path.to_string_lossy().into_owned();
+        tokio::fs::write(&path, content).await.unwrap();
+        let ranges = partition_wordlist(&path, n).await.unwrap();
+        assert_eq!(ranges.len(), n);
+        let mut words = Vec::new();
+        for range in ranges {
+            visit_words(&path, range, |_, word| {
+                words.push(String::from_utf8(word.to_vec()).unwrap());
+                true
+            })
+            .unwrap();
+        }
+        tokio::fs::remove_file(&path).await.unwrap();
+        words
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn partitions_visit_every_line_once() {
+        let long = "x".repeat(2 * BLOCK_SIZE as usize + 3);
+        let contents = [
+            String::new(),
+            "a\nbb\nccc\n".to_string(),
+            "a\nbb\nccc".to_string(),
+            "a\r\nbb\r\n\r\nccc\r\n".to_string(),
+            "a\r\nbb\r\nccc".to_string(),
+            "\n\n\n".to_string(),
+            format!("a\n{long}\nbb\n"),
+            format!("{long}\r\na\n{long}"),
+        ];
+        for content in &contents {
+            let lines = content.lines().collect::<Vec<_>>();
+            for n in [1, 2, 3, 5, 8, 16] {
+                assert_eq!(visit_partitions(content, n).await, lines, "{n} partitions");
+            }
+        }
+    }
 }
@@ -0,0 +1,113 @@
+use eyre::{bail, eyre, Result};
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SPECIAL: &[u8] = b" !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+fn builtin(placeholder: u8) -> Option<Vec<u8>> {
+    let charset = match placeholder {
+        b'l' => LOWER.to_vec(),
+        b'u' => UPPER.to_vec(),
+        b'd' => DIGITS.to_vec(),
+        b's' => SPECIAL.to_vec(),
+        b'a' => [LOWER, UPPER, DIGITS, SPECIAL].concat(),
+        b'b' => (0..=255).collect(),
+        _ => return None,
+    };
+    Some(charset)
+}
+
+fn expand(placeholder: u8, custom: &[Option<Vec<u8>>]) -> Result<Vec<u8>> {
+    if let Some(charset) = builtin(placeholder) {
+        return Ok(charset);
+    }
+    match placeholder {
+        b'?' => Ok(vec![b'?']),
+        b'1'..=b'4' => custom
+            .get((placeholder - b'1') as usize)
+            .cloned()
+            .flatten()
+            .ok_or_else(|| eyre!("custom charset ?{} is not defined", placeholder as char)),
+        _ => bail!("unknown placeholder ?{}", placeholder as char),
+    }
+}
+
+fn tokens(spec: &str, custom: &[Option<Vec<u8>>]) -> Result<Vec<Vec<u8>>> {
+    let mut tokens = Vec::new();
+    let mut bytes = spec.bytes();
+    while let Some(b) = bytes.next() {
+        if b == b'?' {
+            let placeholder = bytes
+                .next()
+                .ok_or_else(|| eyre!("dangling '?' at the end of {spec:?}"))?;
+            tokens.push(expand(placeholder, custom)?);
+        } else {
+            tokens.push(vec![b]);
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_charset(spec: &str) -> Result<Vec<u8>> {
+    let mut charset = tokens(spec, &[])?.concat();
+    charset.sort_unstable();
+    charset.dedup();
+    if charset.is_empty() {
+        bail!("empty custom charset");
+    }
+    Ok(charset)
+}
+
+pub fn parse_mask(mask: &str, custom: &[Option<String>]) -> Result<Vec<Vec<u8>>> {
+    let custom = custom
+        .iter()
+        .map(|spec| spec.as_deref().map(parse_charset).transpose())
+        .collect::<Result<Vec<_>>>()?;
+    tokens(mask, &custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_expand_to_positions() {
+        let custom = [Some("ab".to_string()), None, Some("?d?l".to_string()), None];
+        for (mask, positions) in [
+            ("?l", vec![LOWER.to_vec()]),
+            ("?u?d", vec![UPPER.to_vec(), DIGITS.to_vec()]),
+            ("?s", vec![SPECIAL.to_vec()]),
+            ("?a", vec![[LOWER, UPPER, DIGITS, SPECIAL].concat()]),
+            ("?b", vec![(0..=255).collect()]),
+            ("a??", vec![b"a".to_vec(), b"?".to_vec()]),
+            ("?1x", vec![b"ab".to_vec(), b"x".to_vec()]),
+            ("?3", vec![[DIGITS, LOWER].concat()]),
+        ] {
+            assert_eq!(parse_mask(mask, &custom).unwrap(), positions, "{mask:?}");
+        }
+    }
+
+    #[test]
+    fn bad_masks_are_rejected() {
+        let custom = [Some("ab".to_string()), None, None, None];
+        for (mask, error) in [
+            ("?l?", "dangling '?'"),
+            ("?x", "unknown placeholder ?x"),
+            ("?2", "custom charset ?2 is not defined"),
+        ] {
+            let e = parse_mask(mask, &custom).unwrap_err().to_string();
+            assert!(e.contains(error), "{mask:?}: {e}");
+        }
+    }
+
+    #[test]
+    fn custom_charsets_cannot_refer_to_custom_charsets() {
+        for spec in ["?1", "?2", "a?4"] {
+            let e = parse_mask("?1", &[Some(spec.to_string()), None, None, None])
+                .unwrap_err()
+                .to_string();
+            assert!(e.contains("is not defined"), "{spec:?}: {e}");
+        }
+    }
+}
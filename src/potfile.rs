@@ -0,0 +1,84 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{ErrorKind, Write},
+};
+
+use eyre::{Result, WrapErr};
+
+pub struct Potfile {
+    path: Option<String>,
+    cracked: HashMap<String, Vec<u8>>,
+}
+
+// Plaintexts that would not survive a round trip through a text line are
+// stored hex encoded, as hashcat does.
+fn encode(plain: &[u8]) -> String {
+    match std::str::from_utf8(plain) {
+        Ok(plain) if !plain.starts_with("$HEX[") && !plain.chars().any(char::is_control) => {
+            plain.to_string()
+        }
+        _ => format!("$HEX[{}]", hex::encode(plain)),
+    }
+}
+
+fn decode(plain: &str) -> Vec<u8> {
+    plain
+        .strip_prefix("$HEX[")
+        .and_then(|hex| hex.strip_suffix(']'))
+        .and_then(|hex| hex::decode(hex).ok())
+        .unwrap_or_else(|| plain.as_bytes().to_vec())
+}
+
+impl Potfile {
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            cracked: HashMap::new(),
+        }
+    }
+
+    // Hashes may contain `:` themselves, so a line is split at the first
+    // colon that leaves one of the hashes we are looking for.
+    pub async fn load(path: &str, hashes: &HashSet<&str>) -> Result<Self> {
+        let pot = match tokio::fs::read(path).await {
+            Ok(pot) => pot,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).wrap_err_with(|| format!("cannot read potfile {path}")),
+        };
+        let mut cracked = HashMap::new();
+        for line in String::from_utf8_lossy(&pot).lines() {
+            let split = line
+                .match_indices(':')
+                .map(|(i, _)| (&line[..i], &line[i + 1..]))
+                .find(|(hash, _)| hashes.contains(hash));
+            if let Some((hash, plain)) = split {
+                cracked.insert(hash.to_string(), decode(plain));
+            }
+        }
+        Ok(Self {
+            path: Some(path.to_string()),
+            cracked,
+        })
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.cracked.contains_key(hash)
+    }
+
+    pub fn show(&self, hash: &str) -> Option<String> {
+        self.cracked.get(hash).map(|plain| encode(plain))
+    }
+
+    pub fn record(&self, hash: &str, plain: &[u8]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut f = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .wrap_err_with(|| format!("cannot open potfile {path}"))?;
+        writeln!(f, "{hash}:{}", encode(plain))
+            .wrap_err_with(|| format!("cannot write to potfile {path}"))
+    }
+}
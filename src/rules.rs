@@ -0,0 +1,403 @@
+use eyre::{bail, eyre, Result, WrapErr};
+
+#[derive(Clone, Copy)]
+enum Op {
+    Noop,
+    Lower,
+    Upper,
+    Capitalize,
+    InvertCapitalize,
+    ToggleAll,
+    Toggle(usize),
+    Reverse,
+    Duplicate,
+    DuplicateN(usize),
+    Reflect,
+    RotateLeft,
+    RotateRight,
+    Append(u8),
+    Prepend(u8),
+    DeleteFirst,
+    DeleteLast,
+    Delete(usize),
+    Extract(usize, usize),
+    Omit(usize, usize),
+    Insert(usize, u8),
+    Overwrite(usize, u8),
+    Truncate(usize),
+    Replace(u8, u8),
+    Purge(u8),
+    DuplicateFirst(usize),
+    DuplicateLast(usize),
+    DuplicateAll,
+    SwapFront,
+    SwapBack,
+    Swap(usize, usize),
+    ShiftLeft(usize),
+    ShiftRight(usize),
+    Increment(usize),
+    Decrement(usize),
+    ReplaceNext(usize),
+    ReplacePrev(usize),
+    DuplicateBlockFront(usize),
+    DuplicateBlockBack(usize),
+    Title,
+    TitleSep(u8),
+    RejectLonger(usize),
+    RejectShorter(usize),
+    RejectNotLength(usize),
+    RejectContains(u8),
+    RejectMissing(u8),
+    RejectFirstNot(u8),
+    RejectLastNot(u8),
+    RejectAtNot(usize, u8),
+    RejectFewer(usize, u8),
+}
+
+impl Op {
+    // Length of the word after a function that can grow it.
+    fn grown_len(self, len: usize) -> usize {
+        match self {
+            Op::Duplicate | Op::Reflect | Op::DuplicateAll => 2 * len,
+            Op::DuplicateN(n) => (n + 1) * len,
+            Op::Append(_) | Op::Prepend(_) | Op::Insert(..) => len + 1,
+            Op::DuplicateFirst(n)
+            | Op::DuplicateLast(n)
+            | Op::DuplicateBlockFront(n)
+            | Op::DuplicateBlockBack(n) => len + n,
+            _ => len,
+        }
+    }
+}
+
+// Like hashcat, functions that would grow a word to this size are skipped.
+const MAX_LEN: usize = 256;
+
+pub struct Rule(Vec<Op>);
+
+fn position(c: u8) -> Result<usize> {
+    match c {
+        b'0'..=b'9' => Ok((c - b'0') as usize),
+        b'A'..=b'Z' => Ok((c - b'A') as usize + 10),
+        _ => bail!("invalid position {:?}", c as char),
+    }
+}
+
+impl Rule {
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut ops = Vec::new();
+        let mut bytes = line.bytes();
+        while let Some(name) = bytes.next() {
+            let mut param = || {
+                bytes
+                    .next()
+                    .ok_or_else(|| eyre!("missing parameter for '{}'", name as char))
+            };
+            let op = match name {
+                b' ' => continue,
+                b':' => Op::Noop,
+                b'l' => Op::Lower,
+                b'u' => Op::Upper,
+                b'c' => Op::Capitalize,
+                b'C' => Op::InvertCapitalize,
+                b't' => Op::ToggleAll,
+                b'T' => Op::Toggle(position(param()?)?),
+                b'r' => Op::Reverse,
+                b'd' => Op::Duplicate,
+                b'p' => Op::DuplicateN(position(param()?)?),
+                b'f' => Op::Reflect,
+                b'{' => Op::RotateLeft,
+                b'}' => Op::RotateRight,
+                b'$' => Op::Append(param()?),
+                b'^' => Op::Prepend(param()?),
+                b'[' => Op::DeleteFirst,
+                b']' => Op::DeleteLast,
+                b'D' => Op::Delete(position(param()?)?),
+                b'x' => Op::Extract(position(param()?)?, position(param()?)?),
+                b'O' => Op::Omit(position(param()?)?, position(param()?)?),
+                b'i' => Op::Insert(position(param()?)?, param()?),
+                b'o' => Op::Overwrite(position(param()?)?, param()?),
+                b'\'' => Op::Truncate(position(param()?)?),
+                b's' => Op::Replace(param()?, param()?),
+                b'@' => Op::Purge(param()?),
+                b'z' => Op::DuplicateFirst(position(param()?)?),
+                b'Z' => Op::DuplicateLast(position(param()?)?),
+                b'q' => Op::DuplicateAll,
+                b'k' => Op::SwapFront,
+                b'K' => Op::SwapBack,
+                b'*' => Op::Swap(position(param()?)?, position(param()?)?),
+                b'L' => Op::ShiftLeft(position(param()?)?),
+                b'R' => Op::ShiftRight(position(param()?)?),
+                b'+' => Op::Increment(position(param()?)?),
+                b'-' => Op::Decrement(position(param()?)?),
+                b'.' => Op::ReplaceNext(position(param()?)?),
+                b',' => Op::ReplacePrev(position(param()?)?),
+                b'y' => Op::DuplicateBlockFront(position(param()?)?),
+                b'Y' => Op::DuplicateBlockBack(position(param()?)?),
+                b'E' => Op::Title,
+                b'e' => Op::TitleSep(param()?),
+                b'<' => Op::RejectLonger(position(param()?)?),
+                b'>' => Op::RejectShorter(position(param()?)?),
+                b'_' => Op::RejectNotLength(position(param()?)?),
+                b'!' => Op::RejectContains(param()?),
+                b'/' => Op::RejectMissing(param()?),
+                b'(' => Op::RejectFirstNot(param()?),
+                b')' => Op::RejectLastNot(param()?),
+                b'=' => Op::RejectAtNot(position(param()?)?, param()?),
+                b'%' => Op::RejectFewer(position(param()?)?, param()?),
+                _ => bail!("unknown rule function '{}'", name as char),
+            };
+            ops.push(op);
+        }
+        Ok(Self(ops))
+    }
+
+    pub fn apply(&self, word: &[u8]) -> Option<Vec<u8>> {
+        let mut w = word.to_vec();
+        for &op in &self.0 {
+            let len = w.len();
+            if op.grown_len(len) >= MAX_LEN {
+                continue;
+            }
+            match op {
+                Op::Lower => w.make_ascii_lowercase(),
+                Op::Upper => w.make_ascii_uppercase(),
+                Op::Capitalize => {
+                    w.make_ascii_lowercase();
+                    w.iter_mut().take(1).for_each(u8::make_ascii_uppercase);
+                }
+                Op::InvertCapitalize => {
+                    w.make_ascii_uppercase();
+                    w.iter_mut().take(1).for_each(u8::make_ascii_lowercase);
+                }
+                Op::ToggleAll => w.iter_mut().for_each(toggle),
+                Op::Toggle(n) if n < len => toggle(&mut w[n]),
+                Op::Reverse => w.reverse(),
+                Op::Duplicate => w = w.repeat(2),
+                Op::DuplicateN(n) => w = w.repeat(n + 1),
+                Op::Reflect => w.extend(reversed(&w)),
+                Op::RotateLeft if len > 0 => w.rotate_left(1),
+                Op::RotateRight if len > 0 => w.rotate_right(1),
+                Op::Append(c) => w.push(c),
+                Op::Prepend(c) => w.insert(0, c),
+                Op::DeleteFirst if len > 0 => {
+                    w.remove(0);
+                }
+                Op::DeleteLast => {
+                    w.pop();
+                }
+                Op::Delete(n) if n < len => {
+                    w.remove(n);
+                }
+                Op::Extract(n, m) if n < len && n + m <= len => w = w[n..n + m].to_vec(),
+                Op::Omit(n, m) if n < len && n + m <= len => {
+                    w.drain(n..n + m);
+                }
+                Op::Insert(n, c) if n <= len => w.insert(n, c),
+                Op::Overwrite(n, c) if n < len => w[n] = c,
+                Op::Truncate(n) => w.truncate(n),
+                Op::Replace(x, y) => w.iter_mut().filter(|c| **c == x).for_each(|c| *c = y),
+                Op::Purge(x) => w.retain(|&c| c != x),
+                Op::DuplicateFirst(n) if len > 0 => {
+                    w.splice(0..0, std::iter::repeat_n(w[0], n));
+                }
+                Op::DuplicateLast(n) if len > 0 => w.extend(std::iter::repeat_n(w[len - 1], n)),
+                Op::DuplicateAll => w = w.iter().flat_map(|&c| [c, c]).collect(),
+                Op::SwapFront if len >= 2 => w.swap(0, 1),
+                Op::SwapBack if len >= 2 => w.swap(len - 2, len - 1),
+                Op::Swap(n, m) if n < len && m < len => w.swap(n, m),
+                Op::ShiftLeft(n) if n < len => w[n] <<= 1,
+                Op::ShiftRight(n) if n < len => w[n] >>= 1,
+                Op::Increment(n) if n < len => w[n] = w[n].wrapping_add(1),
+                Op::Decrement(n) if n < len => w[n] = w[n].wrapping_sub(1),
+                Op::ReplaceNext(n) if n + 1 < len => w[n] = w[n + 1],
+                Op::ReplacePrev(n) if n >= 1 && n < len => w[n] = w[n - 1],
+                Op::DuplicateBlockFront(n) if n <= len => {
+                    w.splice(0..0, w[..n].to_vec());
+                }
+                Op::DuplicateBlockBack(n) if n <= len => w.extend(w[len - n..].to_vec()),
+                Op::Title => title(&mut w, b' '),
+                Op::TitleSep(sep) => title(&mut w, sep),
+                Op::RejectLonger(n) if len > n => return None,
+                Op::RejectShorter(n) if len < n => return None,
+                Op::RejectNotLength(n) if len != n => return None,
+                Op::RejectContains(x) if w.contains(&x) => return None,
+                Op::RejectMissing(x) if !w.contains(&x) => return None,
+                Op::RejectFirstNot(x) if w.first() != Some(&x) => return None,
+                Op::RejectLastNot(x) if w.last() != Some(&x) => return None,
+                Op::RejectAtNot(n, x) if w.get(n) != Some(&x) => return None,
+                Op::RejectFewer(n, x) if w.iter().filter(|&&c| c == x).count() < n => return None,
+                _ => {}
+            }
+        }
+        Some(w)
+    }
+}
+
+fn reversed(w: &[u8]) -> Vec<u8> {
+    w.iter().rev().copied().collect()
+}
+
+fn toggle(c: &mut u8) {
+    if c.is_ascii_lowercase() {
+        c.make_ascii_uppercase();
+    } else {
+        c.make_ascii_lowercase();
+    }
+}
+
+fn title(w: &mut [u8], sep: u8) {
+    w.make_ascii_lowercase();
+    let mut start = true;
+    for c in w.iter_mut() {
+        if start {
+            c.make_ascii_uppercase();
+        }
+        start = *c == sep;
+    }
+}
+
+pub struct Rules(Vec<Rule>);
+
+impl Rules {
+    pub async fn load(path: &str) -> Result<Self> {
+        let rules = tokio::fs::read_to_string(path).await?;
+        let mut parsed = Vec::new();
+        for (i, line) in rules.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let rule =
+                Rule::parse(line).wrap_err_with(|| format!("{path}:{}: invalid rule", i + 1))?;
+            parsed.push(rule);
+        }
+        if parsed.is_empty() {
+            bail!("no rules found in {path}");
+        }
+        Ok(Self(parsed))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn apply(&self, word: &[u8]) -> Vec<Vec<u8>> {
+        self.0.iter().filter_map(|rule| rule.apply(word)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(rule: &str, word: &[u8]) -> Option<Vec<u8>> {
+        Rule::parse(rule).unwrap().apply(word)
+    }
+
+    // The examples from hashcat's rule documentation.
+    #[test]
+    fn rules_match_hashcat() {
+        for (rule, word, expected) in [
+            (":", "p@ssW0rd", &b"p@ssW0rd"[..]),
+            ("l", "p@ssW0rd", b"p@ssw0rd"),
+            ("u", "p@ssW0rd", b"P@SSW0RD"),
+            ("c", "p@ssW0rd", b"P@ssw0rd"),
+            ("C", "p@ssW0rd", b"p@SSW0RD"),
+            ("t", "p@ssW0rd", b"P@SSw0RD"),
+            ("T3", "p@ssW0rd", b"p@sSW0rd"),
+            ("r", "p@ssW0rd", b"dr0Wss@p"),
+            ("d", "p@ssW0rd", b"p@ssW0rdp@ssW0rd"),
+            ("p2", "p@ssW0rd", b"p@ssW0rdp@ssW0rdp@ssW0rd"),
+            ("f", "p@ssW0rd", b"p@ssW0rddr0Wss@p"),
+            ("{", "p@ssW0rd", b"@ssW0rdp"),
+            ("}", "p@ssW0rd", b"dp@ssW0r"),
+            ("$1$2", "p@ssW0rd", b"p@ssW0rd12"),
+            ("^2^1", "p@ssW0rd", b"12p@ssW0rd"),
+            ("[", "p@ssW0rd", b"@ssW0rd"),
+            ("]", "p@ssW0rd", b"p@ssW0r"),
+            ("D3", "p@ssW0rd", b"p@sW0rd"),
+            ("x04", "p@ssW0rd", b"p@ss"),
+            ("x58", "p@ssW0rd", b"p@ssW0rd"),
+            ("O12", "p@ssW0rd", b"psW0rd"),
+            ("O58", "p@ssW0rd", b"p@ssW0rd"),
+            ("i4!", "p@ssW0rd", b"p@ss!W0rd"),
+            ("i8!", "p@ssW0rd", b"p@ssW0rd!"),
+            ("i9!", "p@ssW0rd", b"p@ssW0rd"),
+            ("o3$", "p@ssW0rd", b"p@s$W0rd"),
+            ("'6", "p@ssW0rd", b"p@ssW0"),
+            ("ss$", "p@ssW0rd", b"p@$$W0rd"),
+            ("@s", "p@ssW0rd", b"p@W0rd"),
+            ("z2", "p@ssW0rd", b"ppp@ssW0rd"),
+            ("Z2", "p@ssW0rd", b"p@ssW0rddd"),
+            ("q", "p@ssW0rd", b"pp@@ssssWW00rrdd"),
+            ("k", "p@ssW0rd", b"@pssW0rd"),
+            ("K", "p@ssW0rd", b"p@ssW0dr"),
+            ("*34", "p@ssW0rd", b"p@sWs0rd"),
+            ("L2", "p@ssW0rd", b"p@\xe6sW0rd"),
+            ("R2", "p@ssW0rd", b"p@9sW0rd"),
+            ("+2", "p@ssW0rd", b"p@tsW0rd"),
+            ("-1", "p@ssW0rd", b"p?ssW0rd"),
+            (".1", "p@ssW0rd", b"psssW0rd"),
+            (",1", "p@ssW0rd", b"ppssW0rd"),
+            ("y2", "p@ssW0rd", b"p@p@ssW0rd"),
+            ("Y2", "p@ssW0rd", b"p@ssW0rdrd"),
+            ("E", "p@ssW0rd w0rld", b"P@ssw0rd W0rld"),
+            ("e-", "p@ssW0rd-w0rld", b"P@ssw0rd-W0rld"),
+            ("c $1 $!", "password", b"Password1!"),
+        ] {
+            assert_eq!(
+                apply(rule, word.as_bytes()).as_deref(),
+                Some(expected),
+                "{rule:?} on {word:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn reject_rules_match_hashcat() {
+        for (rule, word, kept) in [
+            ("<8", "p@ssW0rd", true),
+            ("<7", "p@ssW0rd", false),
+            (">8", "p@ssW0rd", true),
+            (">9", "p@ssW0rd", false),
+            ("_8", "p@ssW0rd", true),
+            ("_7", "p@ssW0rd", false),
+            ("!z", "p@ssW0rd", true),
+            ("!@", "p@ssW0rd", false),
+            ("/@", "p@ssW0rd", true),
+            ("/z", "p@ssW0rd", false),
+            ("(p", "p@ssW0rd", true),
+            ("(@", "p@ssW0rd", false),
+            (")d", "p@ssW0rd", true),
+            (")r", "p@ssW0rd", false),
+            ("=1@", "p@ssW0rd", true),
+            ("=1s", "p@ssW0rd", false),
+            ("%2s", "p@ssW0rd", true),
+            ("%3s", "p@ssW0rd", false),
+            ("$! /!", "p@ssW0rd", true),
+        ] {
+            assert_eq!(
+                apply(rule, word.as_bytes()).is_some(),
+                kept,
+                "{rule:?} on {word:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn growth_is_capped() {
+        let word = apply("p9p9p9p9p9p9p9", b"password").unwrap();
+        assert_eq!(word.len(), 80);
+        let word = apply(&"d".repeat(20), b"password").unwrap();
+        assert_eq!(word.len(), 128);
+        let word = apply(&"$a".repeat(300), b"").unwrap();
+        assert_eq!(word.len(), MAX_LEN - 1);
+    }
+
+    #[test]
+    fn bad_rules_are_rejected() {
+        for rule in ["T", "xa", "s1", "?"] {
+            assert!(Rule::parse(rule).is_err(), "{rule:?}");
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::BTreeSet;
+
+use eyre::{bail, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    #[serde(skip)]
+    path: String,
+    args: Vec<String>,
+    // Next position and end of each worker's share, a byte offset into the
+    // wordlist or an index into the keyspace.
+    workers: Vec<(u64, u64)>,
+    cracked: BTreeSet<String>,
+}
+
+impl Session {
+    pub fn new(name: &str, args: Vec<String>) -> Result<Self> {
+        let path = format!("{name}.session");
+        if std::path::Path::new(&path).exists() {
+            bail!("session file {path} already exists, resume it with --restore or remove it");
+        }
+        Ok(Self {
+            path,
+            args,
+            workers: Vec::new(),
+            cracked: BTreeSet::new(),
+        })
+    }
+
+    pub async fn load(name: &str) -> Result<Self> {
+        let path = format!("{name}.session");
+        let session = tokio::fs::read(&path)
+            .await
+            .wrap_err_with(|| format!("cannot read session file {path}"))?;
+        let session: Session = serde_json::from_slice(&session)
+            .wrap_err_with(|| format!("invalid session file {path}"))?;
+        Ok(Self { path, ..session })
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn workers(&self) -> Option<Vec<(u64, u64)>> {
+        match self.workers.is_empty() {
+            true => None,
+            false => Some(self.workers.clone()),
+        }
+    }
+
+    pub fn cracked(&self) -> &BTreeSet<String> {
+        &self.cracked
+    }
+
+    // Written to a temporary file first so a crash mid-write leaves the
+    // previous checkpoint intact.
+    pub fn save(
+        &mut self,
+        workers: Vec<(u64, u64)>,
+        cracked: impl IntoIterator<Item = String>,
+    ) -> Result<()> {
+        self.workers = workers;
+        self.cracked.extend(cracked);
+        let tmp = format!("{}.tmp", self.path);
+        std::fs::write(&tmp, serde_json::to_vec(self)?)
+            .wrap_err_with(|| format!("cannot write session file {tmp}"))?;
+        std::fs::rename(&tmp, &self.path)
+            .wrap_err_with(|| format!("cannot write session file {}", self.path))
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).wrap_err_with(|| format!("cannot remove session file {}", self.path))
+            }
+            _ => Ok(()),
+        }
+    }
+}
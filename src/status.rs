@@ -0,0 +1,267 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant},
+};
+
+pub use listener::listen;
+
+pub enum Event {
+    // Only key presses and SIGUSR1 ask for a status line.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Status,
+    Interrupt(i32),
+    Stop,
+}
+
+// Positions are byte offsets into the wordlist or indices into the keyspace,
+// so the share already consumed also covers work done before a restore.
+pub struct Progress {
+    ranges: Vec<(u64, u64)>,
+    positions: Vec<AtomicU64>,
+    tested: Vec<AtomicU64>,
+    size: u64,
+    remaining: u64,
+    hashes: usize,
+    started: Instant,
+}
+
+pub fn si(n: f64) -> String {
+    match n {
+        n if n >= 1e9 => format!("{:.2}G", n / 1e9),
+        n if n >= 1e6 => format!("{:.2}M", n / 1e6),
+        n if n >= 1e3 => format!("{:.1}k", n / 1e3),
+        n => format!("{n:.0}"),
+    }
+}
+
+fn hms(d: Duration) -> String {
+    let s = d.as_secs();
+    format!("{}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+}
+
+impl Progress {
+    pub fn new(ranges: Vec<(u64, u64)>, hashes: usize) -> Self {
+        let positions = ranges.iter().map(|&(start, _)| start.into()).collect();
+        let tested = ranges.iter().map(|_| 0.into()).collect();
+        let size = ranges.iter().map(|&(_, end)| end).max().unwrap_or(0);
+        let remaining = ranges.iter().map(|&(start, end)| end - start).sum();
+        Self {
+            ranges,
+            positions,
+            tested,
+            size,
+            remaining,
+            hashes,
+            started: Instant::now(),
+        }
+    }
+
+    // Each worker is the only writer of its own counters, so a plain load and
+    // store is enough and keeps locked instructions out of the hot loop.
+    pub fn advance(&self, worker: usize, position: u64) {
+        let tested = &self.tested[worker];
+        self.positions[worker].store(position, Ordering::Relaxed);
+        tested.store(tested.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+    }
+
+    pub fn finish(&self, worker: usize) {
+        self.positions[worker].store(self.ranges[worker].1, Ordering::Relaxed);
+    }
+
+    pub fn tested(&self) -> u64 {
+        self.tested.iter().map(|n| n.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn workers(&self) -> Vec<(u64, u64)> {
+        self.positions
+            .iter()
+            .zip(&self.ranges)
+            .map(|(position, &(_, end))| (position.load(Ordering::Relaxed), end))
+            .collect()
+    }
+
+    pub fn status(&self, recovered: usize) -> String {
+        let elapsed = self.started.elapsed();
+        let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let tested = self
+            .tested
+            .iter()
+            .map(|n| n.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+        let speeds = tested
+            .iter()
+            .map(|&n| si(n as f64 / secs))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let total = tested.iter().sum::<u64>();
+        let remaining = self
+            .workers()
+            .iter()
+            .map(|&(position, end)| end - position)
+            .sum::<u64>();
+        let done = match self.size {
+            0 => 100.0,
+            size => (size - remaining) as f64 * 100.0 / size as f64,
+        };
+        let eta = match self.remaining - remaining {
+            0 => "-".to_string(),
+            consumed => hms(elapsed.mul_f64(remaining as f64 / consumed as f64)),
+        };
+        format!(
+            "[{}] {done:.2}% | {total} tested | {} H/s ({speeds}) | ETA {eta} | {recovered}/{} recovered",
+            hms(elapsed),
+            si(total as f64 / secs),
+            self.hashes
+        )
+    }
+}
+
+#[cfg(unix)]
+mod listener {
+    use std::{
+        io::{IsTerminal, Read},
+        sync::mpsc::Sender,
+    };
+
+    use eyre::Result;
+    use signal_hook::{
+        consts::{SIGINT, SIGTERM, SIGUSR1},
+        iterator::{backend::Handle, Signals},
+    };
+
+    use super::Event;
+
+    // Reading single key presses needs line buffering and echo turned off,
+    // which is only safe while we own the foreground of the terminal.
+    fn unbuffer_input() -> Option<libc::termios> {
+        if !std::io::stdin().is_terminal() {
+            return None;
+        }
+        unsafe {
+            if libc::tcgetpgrp(libc::STDIN_FILENO) != libc::getpgrp() {
+                return None;
+            }
+            let mut original = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+            let mut unbuffered = original;
+            unbuffered.c_lflag &= !(libc::ICANON | libc::ECHO);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &unbuffered) != 0 {
+                return None;
+            }
+            Some(original)
+        }
+    }
+
+    fn restore(terminal: &libc::termios) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, terminal);
+        }
+    }
+
+    pub struct Listener {
+        terminal: Option<libc::termios>,
+        signals: Handle,
+    }
+
+    pub fn listen(events: Sender<Event>) -> Result<Listener> {
+        let terminal = unbuffer_input();
+        let mut signals = Signals::new([SIGUSR1, SIGINT, SIGTERM])?;
+        let handle = signals.handle();
+        let status = events.clone();
+        std::thread::spawn(move || {
+            let mut interrupted = false;
+            for signal in signals.forever() {
+                if signal == SIGUSR1 {
+                    let _ = status.send(Event::Status);
+                    continue;
+                }
+                // The first interrupt stops cracking after a last checkpoint,
+                // another one quits straight away.
+                if !interrupted && status.send(Event::Interrupt(signal)).is_ok() {
+                    interrupted = true;
+                    continue;
+                }
+                if let Some(terminal) = &terminal {
+                    restore(terminal);
+                }
+                std::process::exit(128 + signal);
+            }
+        });
+        if terminal.is_some() {
+            std::thread::spawn(move || {
+                let mut key = [0];
+                while let Ok(1) = std::io::stdin().read(&mut key) {
+                    if matches!(key[0], b's' | b'S') && events.send(Event::Status).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+        Ok(Listener {
+            terminal,
+            signals: handle,
+        })
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            self.signals.close();
+            if let Some(terminal) = &self.terminal {
+                restore(terminal);
+            }
+        }
+    }
+}
+
+// Without terminal control or SIGUSR1 only Ctrl-C is caught. Busy workers can
+// starve the main runtime, so it waits on a runtime of its own.
+#[cfg(not(unix))]
+mod listener {
+    use std::sync::mpsc::Sender;
+
+    use eyre::Result;
+    use tokio::sync::oneshot;
+
+    use super::Event;
+
+    const SIGINT: i32 = 2;
+
+    pub struct Listener(Option<oneshot::Sender<()>>);
+
+    async fn interrupts(events: Sender<Event>) {
+        let mut interrupted = false;
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if !interrupted && events.send(Event::Interrupt(SIGINT)).is_ok() {
+                interrupted = true;
+                continue;
+            }
+            std::process::exit(128 + SIGINT);
+        }
+    }
+
+    pub fn listen(events: Sender<Event>) -> Result<Listener> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (stop, stopped) = oneshot::channel();
+        std::thread::spawn(move || {
+            runtime.block_on(async {
+                tokio::select! {
+                    _ = stopped => {}
+                    _ = interrupts(events) => {}
+                }
+            })
+        });
+        Ok(Listener(Some(stop)))
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            if let Some(stop) = self.0.take() {
+                let _ = stop.send(());
+            }
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    iter,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use eyre::Result;
+
+use crate::{
+    hash::{Hash, HashMode},
+    status::Progress,
+    Entry, Targets,
+};
+
+pub const BLOCK_SIZE: u64 = 1 << 20;
+const BATCH_SIZE: usize = 256;
+
+// Candidates packed end to end, each with the position to resume from.
+#[derive(Default)]
+struct Batch {
+    words: Vec<u8>,
+    ends: Vec<(usize, u64)>,
+}
+
+impl Batch {
+    fn iter(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        let starts = iter::once(0).chain(self.ends.iter().map(|&(end, _)| end));
+        self.ends
+            .iter()
+            .zip(starts)
+            .map(|(&(end, position), start)| (position, &self.words[start..end]))
+    }
+
+    fn clear(&mut self) {
+        self.words.clear();
+        self.ends.clear();
+    }
+}
+
+// Collects candidates from a source and hands them to the worker a batch at a
+// time.
+pub struct Visit<'a> {
+    batch: Batch,
+    hash: &'a mut dyn FnMut(&Batch) -> bool,
+    stopped: bool,
+}
+
+impl Visit<'_> {
+    // Queues a candidate, false once the worker should stop.
+    pub fn push(&mut self, position: u64, word: &[u8]) -> bool {
+        self.batch.words.extend_from_slice(word);
+        self.batch.ends.push((self.batch.words.len(), position));
+        self.batch.ends.len() < BATCH_SIZE || self.flush()
+    }
+
+    fn flush(&mut self) -> bool {
+        self.stopped = !(self.hash)(&self.batch);
+        self.batch.clear();
+        !self.stopped
+    }
+}
+
+// One worker's share of the candidates.
+pub type Source = Box<dyn FnOnce(&mut Visit) -> Result<()> + Send>;
+
+pub type OnHit<'a> = &'a (dyn Fn(HashMode, &Entry, &[u8]) -> Result<()> + Sync);
+
+pub fn source(source: impl FnOnce(&mut Visit) -> Result<()> + Send + 'static) -> Source {
+    Box::new(source)
+}
+
+// Reads a share of the wordlist a block at a time and hands out every line as
+// a slice of the block, so words are never copied.
+pub fn visit_words(
+    path: &str,
+    (start, end): (u64, u64),
+    mut visit: impl FnMut(u64, &[u8]) -> bool,
+) -> Result<()> {
+    let mut f = File::open(path)?;
+    f.seek(SeekFrom::Start(start))?;
+    let mut r = f.take(end - start);
+    let mut block = Vec::new();
+    let mut offset = start;
+    loop {
+        let read = r.by_ref().take(BLOCK_SIZE).read_to_end(&mut block)?;
+        let len = match block.iter().rposition(|&b| b == b'\n') {
+            _ if read == 0 => block.len(),
+            Some(i) => i + 1,
+            // A line longer than the block, keep reading.
+            None => continue,
+        };
+        for line in block[..len].split_inclusive(|&b| b == b'\n') {
+            let word = line.strip_suffix(b"\n").unwrap_or(line);
+            if !visit(offset, word.strip_suffix(b"\r").unwrap_or(word)) {
+                return Ok(());
+            }
+            offset += line.len() as u64;
+        }
+        if read == 0 {
+            return Ok(());
+        }
+        block.drain(..len);
+    }
+}
+
+// False once `done` is set.
+fn hash_batch(
+    worker: usize,
+    batch: &Batch,
+    targets: &Targets,
+    progress: &Progress,
+    done: &AtomicBool,
+    on_hit: OnHit,
+    digest: &mut Hash,
+) -> Result<bool> {
+    for (position, password) in batch.iter() {
+        if done.load(Ordering::Relaxed) {
+            return Ok(false);
+        }
+        progress.advance(worker, position);
+        for ((mode, params), hashes) in targets {
+            for entry in mode
+                .verify(password, params, hashes, digest)
+                .unwrap_or_default()
+            {
+                on_hit(*mode, entry, password)?;
+            }
+        }
+    }
+    Ok(true)
+}
+
+// Hashes every source on its own thread until they run out or `done` is set.
+pub fn run(
+    targets: &Targets,
+    sources: Vec<Source>,
+    progress: &Progress,
+    done: &AtomicBool,
+    on_hit: OnHit,
+) -> Result<()> {
+    std::thread::scope(|s| {
+        let workers = sources
+            .into_iter()
+            .enumerate()
+            .map(|(i, source)| {
+                s.spawn(move || {
+                    let mut failed = None;
+                    let mut digest = Hash::new();
+                    let mut hash = |batch: &Batch| {
+                        let hashed =
+                            hash_batch(i, batch, targets, progress, done, on_hit, &mut digest);
+                        hashed.unwrap_or_else(|e| {
+                            failed = Some(e);
+                            false
+                        })
+                    };
+                    let mut visit = Visit {
+                        batch: Batch::default(),
+                        hash: &mut hash,
+                        stopped: false,
+                    };
+                    let visited = source(&mut visit).map(|()| {
+                        if !visit.stopped {
+                            visit.flush();
+                        }
+                    });
+                    let result = visited.and(failed.map_or(Ok(()), Err));
+                    match result {
+                        Ok(()) if !done.load(Ordering::Relaxed) => progress.finish(i),
+                        Ok(()) => {}
+                        Err(_) => done.store(true, Ordering::Relaxed),
+                    }
+                    result
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().unwrap())
+    })
+}